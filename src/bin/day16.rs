@@ -1,60 +1,168 @@
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, Solver};
+use num_bigint::BigUint;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+use std::path::Path;
 
 struct Day16Solver {
-    root: Vec<bool>,
+    root: Vec<u8>,
 }
 
 impl Solver<u64> for Day16Solver {
     fn new(problem: &str) -> Self {
-        let root: Vec<bool> = problem
-            .chars()
-            .map(|ch| ch.to_digit(16).unwrap() as u8)
-            .flat_map(|n| vec![n & 8 != 0, n & 4 != 0, n & 2 != 0, n & 1 != 0])
-            .collect();
+        let mut root = vec![];
+        HexReader::new(problem.as_bytes())
+            .read_to_end(&mut root)
+            .unwrap();
         Self { root }
     }
 
     fn solve1(&self) -> Option<u64> {
-        let mut parser = Parser::new(&self.root);
-        let packet = parser.parse();
+        let mut parser = Parser::new(&self.root[..]);
+        let packet = parser.parse().unwrap();
         Some(packet.version_sums())
     }
 
     fn solve2(&self) -> Option<u64> {
-        let mut parser = Parser::new(&self.root);
-        let packet = parser.parse();
-        Some(packet.calculate())
+        let mut parser = Parser::new(&self.root[..]);
+        let packet = parser.parse().unwrap();
+        packet.calculate()
     }
 }
 
-struct Parser<'a> {
+impl Day16Solver {
+    fn solve2_big(&self) -> BigUint {
+        let mut parser = Parser::new(&self.root[..]);
+        let packet = parser.parse().unwrap();
+        packet.calculate_big()
+    }
+}
+
+/// Adapts a reader of ASCII hex digits into a reader of the packed bytes
+/// they encode, so a transmission can be decoded without loading it whole.
+struct HexReader<R: BufRead> {
+    inner: io::Bytes<R>,
+}
+
+impl<R: BufRead> HexReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner: inner.bytes(),
+        }
+    }
+
+    fn next_digit(&mut self) -> io::Result<Option<u8>> {
+        for byte in &mut self.inner {
+            let byte = byte?;
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            return match (byte as char).to_digit(16) {
+                Some(digit) => Ok(Some(digit as u8)),
+                None => Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid hex digit {:?}", byte as char),
+                )),
+            };
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Read for HexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            let high = match self.next_digit()? {
+                Some(digit) => digit,
+                None => break,
+            };
+            // an odd trailing digit is padded out with zero bits
+            let low = self.next_digit()?.unwrap_or(0);
+            buf[n] = high << 4 | low;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+/// Reads a byte stream one bit at a time, most significant bit first.
+struct BitReader<R: Read> {
+    inner: R,
+    current: u8,
+    remaining: u8,
     position: usize,
-    data: &'a [bool],
 }
 
-impl<'a> Parser<'a> {
-    fn new(data: &'a [bool]) -> Self {
-        Self { data, position: 0 }
+impl<R: Read> BitReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            current: 0,
+            remaining: 0,
+            position: 0,
+        }
     }
 
-    fn parse(&mut self) -> Packet {
-        self.parse_packet()
+    fn read_bool(&mut self) -> io::Result<bool> {
+        if self.remaining == 0 {
+            let mut buf = [0];
+            self.inner.read_exact(&mut buf)?;
+            self.current = buf[0];
+            self.remaining = 8;
+        }
+
+        self.remaining -= 1;
+        self.position += 1;
+        Ok(self.current >> self.remaining & 1 != 0)
     }
 
-    fn parse_packet(&mut self) -> Packet {
-        let version = self.parse_uint(3) as u8;
-        let type_id = self.parse_uint(3) as u8;
+    fn read_uint(&mut self, n: usize) -> io::Result<u64> {
+        assert!(n <= 64);
+
+        let mut result = 0;
+        for _ in 0..n {
+            result = result << 1 | self.read_bool()? as u64;
+        }
+        Ok(result)
+    }
+}
+
+struct Parser<R: Read> {
+    bits: BitReader<R>,
+}
+
+impl<R: Read> Parser<R> {
+    fn new(data: R) -> Self {
+        Self {
+            bits: BitReader::new(data),
+        }
+    }
+
+    fn parse(&mut self) -> io::Result<Packet> {
+        self.parse_with(|_| {})
+    }
+
+    /// Parse a single packet, calling `emit` with each packet (including
+    /// every sub-packet) as soon as it has been completely decoded.
+    fn parse_with<F: FnMut(&Packet)>(&mut self, mut emit: F) -> io::Result<Packet> {
+        self.parse_packet(&mut emit)
+    }
 
-        if type_id == 4 {
+    fn parse_packet<F: FnMut(&Packet)>(&mut self, emit: &mut F) -> io::Result<Packet> {
+        let version = self.bits.read_uint(3)? as u8;
+        let type_id = self.bits.read_uint(3)? as u8;
+
+        let packet = if type_id == 4 {
             // literal
             let mut chunks = vec![];
 
-            while self.parse_bool() {
-                let chunk = self.parse_uint(4) as u8;
+            while self.bits.read_bool()? {
+                let chunk = self.bits.read_uint(4)? as u8;
                 chunks.push(chunk);
             }
 
-            let chunk = self.parse_uint(4) as u8;
+            let chunk = self.bits.read_uint(4)? as u8;
             chunks.push(chunk);
 
             Packet {
@@ -63,20 +171,27 @@ impl<'a> Parser<'a> {
             }
         } else {
             // operator
-            let subpackets = if self.parse_bool() {
+            let subpackets = if self.bits.read_bool()? {
                 // sub-packet length
-                let length = self.parse_uint(11);
-                (0..length).map(|_| self.parse_packet()).collect()
+                let length = self.bits.read_uint(11)?;
+                (0..length)
+                    .map(|_| self.parse_packet(emit))
+                    .collect::<io::Result<_>>()?
             } else {
                 // total bit length
-                let length = self.parse_uint(15) as usize;
-                let target = self.position + length;
+                let length = self.bits.read_uint(15)? as usize;
+                let target = self.bits.position + length;
 
                 let mut subpackets = vec![];
-                while self.position < target {
-                    subpackets.push(self.parse_packet())
+                while self.bits.position < target {
+                    subpackets.push(self.parse_packet(emit)?)
+                }
+                if self.bits.position != target {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "sub-packets overran their total bit length",
+                    ));
                 }
-                assert_eq!(target, self.position);
                 subpackets
             };
 
@@ -88,50 +203,40 @@ impl<'a> Parser<'a> {
                 5 => Operator::Greater,
                 6 => Operator::Less,
                 7 => Operator::Equal,
-                _ => panic!("invalid operator"),
+                _ => unreachable!(),
             };
 
+            // comparisons need exactly two operands, and min/max at least one
+            let valid = match op {
+                Operator::Greater | Operator::Less | Operator::Equal => subpackets.len() == 2,
+                Operator::Minimum | Operator::Maximum => !subpackets.is_empty(),
+                Operator::Sum | Operator::Product => true,
+            };
+            if !valid {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} packet has {} sub-packets", op, subpackets.len()),
+                ));
+            }
+
             Packet {
                 version,
                 data: PacketData::Operator { op, subpackets },
             }
-        }
-    }
+        };
 
-    fn parse_uint(&mut self, n: usize) -> u64 {
-        assert!(n <= 64);
-
-        let contents = self.parse_data(n);
-        contents
-            .iter()
-            .fold(0, |acc, on| acc << 1 | if *on { 1 } else { 0 })
-    }
-
-    fn parse_data(&mut self, n: usize) -> Vec<bool> {
-        assert!(self.position < self.data.len());
-
-        let start = self.position;
-        let end = usize::min(self.position + n, self.data.len());
-        let mut data = self.data[start..end].to_vec();
-        while data.len() < n {
-            data.push(false);
-        }
-
-        self.position += n;
-        data
-    }
-
-    fn parse_bool(&mut self) -> bool {
-        self.position += 1;
-        self.data[self.position - 1]
+        emit(&packet);
+        Ok(packet)
     }
 }
 
+#[derive(Debug)]
 struct Packet {
     version: u8,
     data: PacketData,
 }
 
+#[derive(Debug)]
 enum PacketData {
     Literal {
         chunks: Vec<u8>,
@@ -142,6 +247,7 @@ enum PacketData {
     },
 }
 
+#[derive(Debug)]
 enum Operator {
     Sum,
     Product,
@@ -163,19 +269,50 @@ impl Packet {
             }
     }
 
-    fn calculate(&self) -> u64 {
+    /// Evaluate the packet, returning None if any intermediate value
+    /// overflows a u64.
+    fn calculate(&self) -> Option<u64> {
+        match &self.data {
+            PacketData::Literal { chunks } => chunks
+                .iter()
+                .try_fold(0u64, |acc, n| Some(acc.checked_mul(16)? | *n as u64)),
+            PacketData::Operator { op, subpackets } => {
+                let results = subpackets
+                    .iter()
+                    .map(Packet::calculate)
+                    .collect::<Option<Vec<u64>>>()?;
+                match *op {
+                    Operator::Sum => results
+                        .into_iter()
+                        .try_fold(0u64, |acc, n| acc.checked_add(n)),
+                    Operator::Product => results
+                        .into_iter()
+                        .try_fold(1u64, |acc, n| acc.checked_mul(n)),
+                    Operator::Minimum => results.into_iter().min(),
+                    Operator::Maximum => results.into_iter().max(),
+                    Operator::Greater => Some((results[0] > results[1]) as u64),
+                    Operator::Less => Some((results[0] < results[1]) as u64),
+                    Operator::Equal => Some((results[0] == results[1]) as u64),
+                }
+            }
+        }
+    }
+
+    /// Evaluate the packet with arbitrary precision, so that any valid
+    /// transmission yields its exact value.
+    fn calculate_big(&self) -> BigUint {
         match &self.data {
-            PacketData::Literal { chunks } => chunks.iter().fold(0, |acc, n| acc << 4 | *n as u64),
+            PacketData::Literal { chunks } => BigUint::from_radix_be(chunks, 16).unwrap(),
             PacketData::Operator { op, subpackets } => {
-                let mut results = subpackets.iter().map(Packet::calculate);
+                let mut results = subpackets.iter().map(Packet::calculate_big);
                 match *op {
                     Operator::Sum => results.sum(),
                     Operator::Product => results.product(),
                     Operator::Minimum => results.min().unwrap(),
                     Operator::Maximum => results.max().unwrap(),
-                    Operator::Greater => (results.next() > results.next()) as u64,
-                    Operator::Less => (results.next() < results.next()) as u64,
-                    Operator::Equal => (results.next() == results.next()) as u64,
+                    Operator::Greater => BigUint::from((results.next() > results.next()) as u8),
+                    Operator::Less => BigUint::from((results.next() < results.next()) as u8),
+                    Operator::Equal => BigUint::from((results.next() == results.next()) as u8),
                 }
             }
         }
@@ -189,13 +326,52 @@ mod day16tests {
     #[test]
     fn test_new() {
         let solver = Day16Solver::new("03AF");
-        assert_eq!(
-            solver.root,
-            vec![
-                false, false, false, false, false, false, true, true, true, false, true, false,
-                true, true, true, true
-            ]
-        );
+        assert_eq!(solver.root, vec![0x03, 0xAF]);
+        let solver = Day16Solver::new("03A");
+        assert_eq!(solver.root, vec![0x03, 0xA0]);
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        let mut bits = BitReader::new(&[0b1010_0110, 0b0100_0000][..]);
+        assert!(bits.read_bool().unwrap());
+        assert_eq!(bits.read_uint(3).unwrap(), 0b010);
+        assert_eq!(bits.read_uint(6).unwrap(), 0b01_1001);
+        assert_eq!(bits.position, 10);
+        assert_eq!(bits.read_uint(6).unwrap(), 0);
+        assert!(bits.read_bool().is_err());
+    }
+
+    #[test]
+    fn test_streaming() {
+        let reader = HexReader::new("8A004A801A8002F478\n".as_bytes());
+        let mut parser = Parser::new(reader);
+        let mut versions = vec![];
+        let packet = parser
+            .parse_with(|packet| versions.push(packet.version))
+            .unwrap();
+        assert_eq!(versions, vec![6, 5, 1, 4]);
+        assert_eq!(packet.version_sums(), 16);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut parser = Parser::new(HexReader::new("8A004A80".as_bytes()));
+        let err = parser.parse().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_operand_count() {
+        // greater than, with a single literal
+        let mut parser = Parser::new(HexReader::new("16004404".as_bytes()));
+        let err = parser.parse().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // minimum, with no sub-packets at all
+        let mut parser = Parser::new(HexReader::new("0A0000".as_bytes()));
+        let err = parser.parse().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
     }
 
     #[test]
@@ -229,8 +405,57 @@ mod day16tests {
         let solver = Day16Solver::new("9C0141080250320F1802104A08");
         assert_eq!(solver.solve2(), Some(1));
     }
+
+    #[test]
+    fn test_overflow() {
+        // literal of 17 chunks, one more than fits in a u64
+        let solver = Day16Solver::new("D3FFFFFFFFFFFFFFFFFFFDE");
+        assert_eq!(solver.solve2(), None);
+        assert_eq!(solver.solve2_big(), BigUint::from(u64::MAX) * 16u8 + 15u8);
+
+        // product of two u64::MAX literals
+        let solver = Day16Solver::new("260084FFFFFFFFFFFFFFFFFFEF13FFFFFFFFFFFFFFFFFFBC");
+        assert_eq!(solver.solve2(), None);
+        assert_eq!(
+            solver.solve2_big(),
+            BigUint::from(u64::MAX) * BigUint::from(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_big_matches_small() {
+        for problem in [
+            "C200B40A82",
+            "04005AC33890",
+            "880086C3E88112",
+            "9C0141080250320F1802104A08",
+        ] {
+            let solver = Day16Solver::new(problem);
+            assert_eq!(
+                Some(solver.solve2_big()),
+                solver.solve2().map(BigUint::from)
+            );
+        }
+    }
 }
 
 fn main() {
-    solve_file::<Day16Solver, u64>("day16.txt");
+    if std::env::args().any(|arg| arg == "--stream") {
+        // decode straight from the file, without buffering the transmission
+        let file = File::open(Path::new("resources").join("day16.txt")).unwrap();
+        let mut parser = Parser::new(HexReader::new(BufReader::new(file)));
+        let packet = parser.parse().unwrap();
+        println!("part 1: {}", packet.version_sums());
+        println!("part 2: {}", packet.calculate_big());
+    } else if std::env::args().any(|arg| arg == "--big") {
+        let solver = Day16Solver::new(&load_file("day16.txt"));
+        println!("part 2: {}", solver.solve2_big());
+    } else {
+        let solver = Day16Solver::new(&load_file("day16.txt"));
+        println!("part 1: {}", solver.solve1().unwrap());
+        match solver.solve2() {
+            Some(result) => println!("part 2: {}", result),
+            None => println!("part 2: overflowed a u64, rerun with --big"),
+        }
+    }
 }
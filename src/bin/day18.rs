@@ -1,7 +1,11 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::iter::Sum;
 use std::ops::Add;
 use std::str;
+use std::thread;
 
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
 
 struct Day18Solver {
     snails: Vec<Snail>,
@@ -9,94 +13,172 @@ struct Day18Solver {
 
 impl Solver<u64> for Day18Solver {
     fn new(problem: &str) -> Self {
-        let snails = problem.split("\n").map(Snail::parse).collect();
+        let snails = problem
+            .split('\n')
+            .map(|line| line.parse().unwrap())
+            .collect();
         Self { snails }
     }
 
     fn solve1(&self) -> Option<u64> {
-        let result = self
-            .snails
-            .clone()
-            .into_iter()
-            .reduce(|left, right| {
-                let mut result = &left + &right;
-                result.reduce_all();
-                result
-            })
-            .unwrap();
+        let result: Snail = self.snails.iter().cloned().sum();
         Some(result.magnitude())
     }
 
     fn solve2(&self) -> Option<u64> {
-        let mut max_magnitude = 0;
-        for snail1 in &self.snails {
-            for snail2 in &self.snails {
-                let mut result = snail1 + snail2;
-                result.reduce_all();
-                max_magnitude = max_magnitude.max(result.magnitude());
+        self.best_pair().map(|(_, _, magnitude)| magnitude)
+    }
+}
+
+impl Day18Solver {
+    /// Find the ordered pair of distinct snail numbers whose sum has the
+    /// largest magnitude, returning their indices and that magnitude.
+    fn best_pair(&self) -> Option<(usize, usize, u64)> {
+        let snails = &self.snails;
+        let n = snails.len();
+        let workers = thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(n.max(1));
+
+        // prefer the earliest pair on ties, so results don't depend on threading
+        let key = |&(i, j, magnitude): &(usize, usize, u64)| (magnitude, Reverse((i, j)));
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|worker| {
+                    scope.spawn(move || {
+                        (worker..n)
+                            .step_by(workers)
+                            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+                            .map(|(i, j)| (i, j, (&snails[i] + &snails[j]).magnitude()))
+                            .max_by_key(key)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().unwrap())
+                .max_by_key(key)
+        })
+    }
+
+    fn print_trace(&self) {
+        let mut snails = self.snails.iter().cloned();
+        let mut acc = match snails.next() {
+            Some(snail) => snail,
+            None => return,
+        };
+        for snail in snails {
+            println!("  {}", acc);
+            println!("+ {}", snail);
+            let sum = Snail::pair(acc, snail);
+            println!("= {}", sum);
+            acc = sum.clone();
+            for step in sum.reduce_trace() {
+                println!("  {}", step);
+                acc = step.result;
             }
+            println!("= {} (magnitude {})", acc, acc.magnitude());
+            println!();
         }
-        Some(max_magnitude)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum SnailItem {
-    Open,
-    Close,
-    Number(u8),
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Snail {
+    Number(u64),
+    Pair(Box<Snail>, Box<Snail>),
 }
 
-#[derive(Debug, PartialEq, Clone)]
-struct Snail {
-    items: Vec<SnailItem>,
+#[derive(Debug, PartialEq, Eq)]
+enum ParseSnailError {
+    UnexpectedChar(usize, char),
+    UnexpectedEnd,
+    InvalidNumber(usize),
 }
 
-impl Snail {
-    fn parse(s: &str) -> Self {
+impl fmt::Display for ParseSnailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSnailError::UnexpectedChar(i, ch) => {
+                write!(f, "unexpected character {:?} at position {}", ch, i)
+            }
+            ParseSnailError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseSnailError::InvalidNumber(i) => write!(f, "invalid number at position {}", i),
+        }
+    }
+}
+
+impl str::FromStr for Snail {
+    type Err = ParseSnailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s: Vec<char> = s.chars().collect();
         let mut i = 0;
+        let snail = Snail::parse_at(&s, &mut i)?;
+        match s.get(i) {
+            Some(ch) => Err(ParseSnailError::UnexpectedChar(i, *ch)),
+            None => Ok(snail),
+        }
+    }
+}
 
-        let mut items = vec![];
-        while i < s.len() {
-            match s[i] {
-                '[' => items.push(SnailItem::Open),
-                ']' => items.push(SnailItem::Close),
-                ',' => {}
-                '0'..='9' => {
-                    let mut j = i + 1;
-                    while j < s.len() && s[j] >= '0' && s[j] <= '9' {
-                        j += 1;
-                    }
-                    items.push(SnailItem::Number(
-                        s[i..j].iter().collect::<String>().parse().unwrap(),
-                    ));
-                    i = j - 1;
-                }
-                _ => unreachable!(),
-            }
-            i += 1;
+impl fmt::Display for Snail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Snail::Number(n) => write!(f, "{}", n),
+            Snail::Pair(left, right) => write!(f, "[{},{}]", left, right),
         }
-        Self { items }
     }
+}
 
-    fn magnitude(&self) -> u64 {
-        let mut stack = vec![];
-        let mut i = 0;
-        while i < self.items.len() {
-            match self.items[i] {
-                SnailItem::Number(n) => stack.push(n as u64),
-                SnailItem::Close => {
-                    let next = 2 * stack.pop().unwrap() + 3 * stack.pop().unwrap();
-                    stack.push(next);
+impl Snail {
+    fn pair(left: Snail, right: Snail) -> Self {
+        Snail::Pair(Box::new(left), Box::new(right))
+    }
+
+    fn parse_at(s: &[char], i: &mut usize) -> Result<Self, ParseSnailError> {
+        let expect = |i: &mut usize, expected: char| match s.get(*i) {
+            Some(ch) if *ch == expected => {
+                *i += 1;
+                Ok(())
+            }
+            Some(ch) => Err(ParseSnailError::UnexpectedChar(*i, *ch)),
+            None => Err(ParseSnailError::UnexpectedEnd),
+        };
+
+        match s.get(*i) {
+            Some('[') => {
+                *i += 1;
+                let left = Snail::parse_at(s, i)?;
+                expect(i, ',')?;
+                let right = Snail::parse_at(s, i)?;
+                expect(i, ']')?;
+                Ok(Snail::pair(left, right))
+            }
+            Some('0'..='9') => {
+                let start = *i;
+                while *i < s.len() && s[*i].is_ascii_digit() {
+                    *i += 1;
                 }
-                _ => {}
+                s[start..*i]
+                    .iter()
+                    .collect::<String>()
+                    .parse()
+                    .map(Snail::Number)
+                    .map_err(|_| ParseSnailError::InvalidNumber(start))
             }
-            i += 1;
+            Some(ch) => Err(ParseSnailError::UnexpectedChar(*i, *ch)),
+            None => Err(ParseSnailError::UnexpectedEnd),
         }
+    }
 
-        assert_eq!(stack.len(), 1);
-        stack[0]
+    fn magnitude(&self) -> u64 {
+        match self {
+            Snail::Number(n) => *n,
+            Snail::Pair(left, right) => 3 * left.magnitude() + 2 * right.magnitude(),
+        }
     }
 
     fn reduce_all(&mut self) {
@@ -107,71 +189,157 @@ impl Snail {
     }
 
     fn reduce(&mut self) -> bool {
-        // explode
-        let mut count = 0;
-        for i in 0..self.items.len() {
-            match self.items[i] {
-                SnailItem::Open => count += 1,
-                SnailItem::Close => count -= 1,
-                SnailItem::Number(n) if count > 4 => {
-                    // explode to left
-                    for j in (0..i).rev() {
-                        if let SnailItem::Number(m) = self.items[j] {
-                            self.items[j] = SnailItem::Number(n + m);
-                            break;
-                        }
-                    }
+        self.reduce_step().is_some()
+    }
 
-                    // shift to next
-                    self.items.remove(i);
-                    let n = if let SnailItem::Number(n) = self.items[i] {
-                        n
-                    } else {
-                        // our input will never nest like this
-                        unreachable!();
-                    };
-
-                    // explode to right
-                    for j in i + 1..self.items.len() {
-                        if let SnailItem::Number(m) = self.items[j] {
-                            self.items[j] = SnailItem::Number(n + m);
-                            break;
-                        }
-                    }
-                    self.items.remove(i);
+    /// Perform a single reduction step, returning which action was taken and
+    /// the path to the pair or number it was applied to.
+    fn reduce_step(&mut self) -> Option<(ReduceAction, Vec<Side>)> {
+        let mut path = vec![];
+        if self.explode_at(&mut path).is_some() {
+            return Some((ReduceAction::Explode, path));
+        }
+        if self.split_at(&mut path) {
+            return Some((ReduceAction::Split, path));
+        }
+        None
+    }
 
-                    // remove open and close
-                    self.items.remove(i);
-                    self.items.remove(i - 1);
+    /// Reduce the number, yielding every intermediate state along the way.
+    fn reduce_trace(self) -> ReduceTrace {
+        ReduceTrace { snail: self }
+    }
 
-                    // add 0
-                    self.items.insert(i - 1, SnailItem::Number(0));
+    fn explode_at(&mut self, path: &mut Vec<Side>) -> Option<(u64, u64)> {
+        match self {
+            Snail::Number(_) => None,
+            Snail::Pair(left, right) => {
+                if path.len() >= 4 {
+                    if let (Snail::Number(l), Snail::Number(r)) = (&**left, &**right) {
+                        let carry = (*l, *r);
+                        *self = Snail::Number(0);
+                        return Some(carry);
+                    }
+                }
 
-                    return true;
+                // a carry of 0 has already been absorbed by a neighbour
+                path.push(Side::Left);
+                if let Some((l, r)) = left.explode_at(path) {
+                    right.add_leftmost(r);
+                    return Some((l, 0));
+                }
+                path.pop();
+                path.push(Side::Right);
+                if let Some((l, r)) = right.explode_at(path) {
+                    left.add_rightmost(l);
+                    return Some((0, r));
                 }
-                _ => {}
+                path.pop();
+                None
             }
         }
+    }
 
-        // split
-        for i in 0..self.items.len() {
-            if let SnailItem::Number(n) = self.items[i] {
-                if n >= 10 {
-                    // split numbers
-                    let half = n / 2;
-                    self.items[i] = SnailItem::Number(half);
-                    self.items.insert(i + 1, SnailItem::Number(n - half));
+    fn add_leftmost(&mut self, value: u64) {
+        match self {
+            Snail::Number(n) => *n += value,
+            Snail::Pair(left, _) => left.add_leftmost(value),
+        }
+    }
 
-                    // insert brackets
-                    self.items.insert(i, SnailItem::Open);
-                    self.items.insert(i + 3, SnailItem::Close);
+    fn add_rightmost(&mut self, value: u64) {
+        match self {
+            Snail::Number(n) => *n += value,
+            Snail::Pair(_, right) => right.add_rightmost(value),
+        }
+    }
 
+    fn split_at(&mut self, path: &mut Vec<Side>) -> bool {
+        match self {
+            Snail::Number(n) if *n >= 10 => {
+                let half = *n / 2;
+                *self = Snail::pair(Snail::Number(half), Snail::Number(*n - half));
+                true
+            }
+            Snail::Number(_) => false,
+            Snail::Pair(left, right) => {
+                path.push(Side::Left);
+                if left.split_at(path) {
                     return true;
                 }
+                path.pop();
+                path.push(Side::Right);
+                if right.split_at(path) {
+                    return true;
+                }
+                path.pop();
+                false
             }
         }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ReduceAction {
+    Explode,
+    Split,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ReduceStep {
+    action: ReduceAction,
+    path: Vec<Side>,
+    result: Snail,
+}
+
+impl fmt::Display for ReduceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = match self.action {
+            ReduceAction::Explode => "explode",
+            ReduceAction::Split => "split",
+        };
+        let path: String = self
+            .path
+            .iter()
+            .map(|side| match side {
+                Side::Left => 'L',
+                Side::Right => 'R',
+            })
+            .collect();
+        write!(f, "after {} at {}: {}", action, path, self.result)
+    }
+}
 
-        false
+struct ReduceTrace {
+    snail: Snail,
+}
+
+impl Iterator for ReduceTrace {
+    type Item = ReduceStep;
+
+    fn next(&mut self) -> Option<ReduceStep> {
+        let (action, path) = self.snail.reduce_step()?;
+        Some(ReduceStep {
+            action,
+            path,
+            result: self.snail.clone(),
+        })
+    }
+}
+
+impl Add for Snail {
+    type Output = Snail;
+
+    fn add(self, other: Self) -> Snail {
+        let mut result = Snail::pair(self, other);
+        result.reduce_all();
+        result
     }
 }
 
@@ -179,13 +347,14 @@ impl Add for &Snail {
     type Output = Snail;
 
     fn add(self, other: Self) -> Snail {
-        let mut items = vec![];
-        items.push(SnailItem::Open);
-        items.extend_from_slice(&self.items);
-        items.extend_from_slice(&other.items);
-        items.push(SnailItem::Close);
+        self.clone() + other.clone()
+    }
+}
 
-        Snail { items }
+impl Sum for Snail {
+    fn sum<I: Iterator<Item = Snail>>(mut iter: I) -> Snail {
+        let first = iter.next().expect("cannot sum zero snail numbers");
+        iter.fold(first, |acc, snail| acc + snail)
     }
 }
 
@@ -194,85 +363,139 @@ mod day18tests {
     use super::*;
     use indoc::indoc;
 
+    fn snail(s: &str) -> Snail {
+        s.parse().unwrap()
+    }
+
     #[test]
     fn test_parse() {
-        let snail = Snail::parse("[1,2]");
         assert_eq!(
-            snail.items,
-            vec![
-                SnailItem::Open,
-                SnailItem::Number(1),
-                SnailItem::Number(2),
-                SnailItem::Close
-            ]
+            snail("[1,2]"),
+            Snail::pair(Snail::Number(1), Snail::Number(2))
         );
-        let snail = Snail::parse("[[1,2],3]");
         assert_eq!(
-            snail.items,
-            vec![
-                SnailItem::Open,
-                SnailItem::Open,
-                SnailItem::Number(1),
-                SnailItem::Number(2),
-                SnailItem::Close,
-                SnailItem::Number(3),
-                SnailItem::Close
-            ]
+            snail("[[1,2],3]"),
+            Snail::pair(
+                Snail::pair(Snail::Number(1), Snail::Number(2)),
+                Snail::Number(3)
+            )
+        );
+        assert_eq!(snail("[10,2]").to_string(), "[10,2]");
+        let s = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]";
+        assert_eq!(snail(s).to_string(), s);
+
+        assert_eq!("[1,2".parse::<Snail>(), Err(ParseSnailError::UnexpectedEnd));
+        assert_eq!(
+            "[1;2]".parse::<Snail>(),
+            Err(ParseSnailError::UnexpectedChar(2, ';'))
+        );
+        assert_eq!(
+            "[1,2]]".parse::<Snail>(),
+            Err(ParseSnailError::UnexpectedChar(5, ']'))
         );
     }
 
     #[test]
     fn test_explode() {
-        let mut snail = Snail::parse("[1,2]");
-        assert!(!snail.reduce());
+        let mut s = snail("[1,2]");
+        assert!(!s.reduce());
 
-        let mut snail = Snail::parse("[[[[[9,8],1],2],3],4]");
-        assert!(snail.reduce());
-        assert_eq!(snail, Snail::parse("[[[[0,9],2],3],4]"));
+        let mut s = snail("[[[[[9,8],1],2],3],4]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[[[[0,9],2],3],4]"));
 
-        let mut snail = Snail::parse("[7,[6,[5,[4,[3,2]]]]]");
-        assert!(snail.reduce());
-        assert_eq!(snail, Snail::parse("[7,[6,[5,[7,0]]]]"));
+        let mut s = snail("[7,[6,[5,[4,[3,2]]]]]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[7,[6,[5,[7,0]]]]"));
 
-        let mut snail = Snail::parse("[[6,[5,[4,[3,2]]]],1]");
-        assert!(snail.reduce());
-        assert_eq!(snail, Snail::parse("[[6,[5,[7,0]]],3]"));
+        let mut s = snail("[[6,[5,[4,[3,2]]]],1]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[[6,[5,[7,0]]],3]"));
 
-        let mut snail = Snail::parse("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]");
-        assert!(snail.reduce());
-        assert_eq!(snail, Snail::parse("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"));
+        let mut s = snail("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"));
 
-        let mut snail = Snail::parse("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]");
-        assert!(snail.reduce());
-        assert_eq!(snail, Snail::parse("[[3,[2,[8,0]]],[9,[5,[7,0]]]]"));
+        let mut s = snail("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[[3,[2,[8,0]]],[9,[5,[7,0]]]]"));
+    }
+
+    #[test]
+    fn test_explode_deep() {
+        // the right element of the exploding pair's parent is itself a pair
+        let mut s = snail("[[[[[1,2],[3,4]],5],6],7]");
+        assert!(s.reduce());
+        assert_eq!(s, snail("[[[[0,[5,4]],5],6],7]"));
+
+        // nested far deeper than a reduced sum can produce
+        let mut s = snail("[[[[[[[1,2],3],4],5],6],7],8]");
+        s.reduce_all();
+        assert_eq!(s, snail("[[[[7,0],[6,7]],7],8]"));
     }
 
     #[test]
     fn test_add() {
-        let left = Snail::parse("[[[[4,3],4],4],[7,[[8,4],9]]]");
-        let right = Snail::parse("[1,1]");
-        let mut result = &left + &right;
-        assert_eq!(
-            result,
-            Snail::parse("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]")
-        );
+        let left = snail("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let right = snail("[1,1]");
+        let mut result = Snail::pair(left.clone(), right.clone());
+        assert_eq!(result, snail("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]"));
         assert!(result.reduce());
-        assert_eq!(result, Snail::parse("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]"));
+        assert_eq!(result, snail("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]"));
         assert!(result.reduce());
-        assert_eq!(result, Snail::parse("[[[[0,7],4],[15,[0,13]]],[1,1]]"));
+        assert_eq!(result, snail("[[[[0,7],4],[15,[0,13]]],[1,1]]"));
         assert!(result.reduce());
-        assert_eq!(result, Snail::parse("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"));
+        assert_eq!(result, snail("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"));
         assert!(result.reduce());
-        assert_eq!(
-            result,
-            Snail::parse("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]")
-        );
+        assert_eq!(result, snail("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]"));
         assert!(result.reduce());
-        assert_eq!(result, Snail::parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
+        assert_eq!(result, snail("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
 
-        let mut result = &left + &right;
-        result.reduce_all();
-        assert_eq!(result, Snail::parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
+        assert_eq!(&left + &right, snail("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
+        assert_eq!(left + right, snail("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
+    }
+
+    #[test]
+    fn test_reduce_trace() {
+        use ReduceAction::*;
+        use Side::*;
+
+        let sum = snail("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+        let steps: Vec<ReduceStep> = sum.reduce_trace().collect();
+        assert_eq!(
+            steps,
+            vec![
+                ReduceStep {
+                    action: Explode,
+                    path: vec![Left, Left, Left, Left],
+                    result: snail("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]"),
+                },
+                ReduceStep {
+                    action: Explode,
+                    path: vec![Left, Right, Right, Left],
+                    result: snail("[[[[0,7],4],[15,[0,13]]],[1,1]]"),
+                },
+                ReduceStep {
+                    action: Split,
+                    path: vec![Left, Right, Left],
+                    result: snail("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"),
+                },
+                ReduceStep {
+                    action: Split,
+                    path: vec![Left, Right, Right, Right],
+                    result: snail("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]"),
+                },
+                ReduceStep {
+                    action: Explode,
+                    path: vec![Left, Right, Right, Right],
+                    result: snail("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"),
+                },
+            ]
+        );
+        assert_eq!(
+            steps[0].to_string(),
+            "after explode at LLLL: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]"
+        );
     }
 
     static EXAMPLE_DATA: &'static str = indoc!(
@@ -300,9 +523,34 @@ mod day18tests {
     fn test_example_part2() {
         let solver = Day18Solver::new(EXAMPLE_DATA.trim());
         assert_eq!(solver.solve2(), Some(3993));
+        assert_eq!(solver.best_pair(), Some((8, 0, 3993)));
+    }
+
+    #[test]
+    fn test_best_pair_distinct() {
+        // adding the first number to itself would give 225
+        let solver = Day18Solver::new("[9,9]\n[1,1]");
+        assert_eq!(solver.best_pair(), Some((0, 1, 145)));
+
+        let solver = Day18Solver::new("[9,9]");
+        assert_eq!(solver.best_pair(), None);
     }
 }
 
 fn main() {
-    solve_file::<Day18Solver, u64>("day18.txt");
+    if std::env::args().any(|arg| arg == "--trace") {
+        let solver = Day18Solver::new(&load_file("day18.txt"));
+        solver.print_trace();
+    } else if std::env::args().any(|arg| arg == "--best-pair") {
+        let solver = Day18Solver::new(&load_file("day18.txt"));
+        if let Some((i, j, magnitude)) = solver.best_pair() {
+            println!("line {} + line {}", i + 1, j + 1);
+            println!("  {}", solver.snails[i]);
+            println!("+ {}", solver.snails[j]);
+            println!("= {}", &solver.snails[i] + &solver.snails[j]);
+            println!("magnitude: {}", magnitude);
+        }
+    } else {
+        solve_file::<Day18Solver, u64>("day18.txt");
+    }
 }
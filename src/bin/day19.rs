@@ -1,9 +1,15 @@
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::path::Path;
 
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
 
 struct Day19Solver {
     scanners: Vec<Scanner>,
+    min_overlap: usize,
 }
 
 impl Solver<u64> for Day19Solver {
@@ -25,25 +31,20 @@ impl Solver<u64> for Day19Solver {
                 Scanner { beacons }
             })
             .collect();
-        Self { scanners }
+        Self {
+            scanners,
+            min_overlap: DEFAULT_MIN_OVERLAP,
+        }
     }
 
     fn solve1(&self) -> Option<u64> {
-        let solves = self.solve();
-
-        // find unique points
-        let mut results = HashSet::new();
-        for solve in &solves {
-            for beacon in &solve.scanner.beacons {
-                results.insert(*beacon);
-            }
-        }
-
-        Some(results.len() as u64)
+        let solves = self.solve().ok()?;
+        let reconstruction = Reconstruction::new(&solves);
+        Some(reconstruction.beacons.len() as u64)
     }
 
     fn solve2(&self) -> Option<u64> {
-        let solves = self.solve();
+        let solves = self.solve().ok()?;
 
         // find the lowest manhattan distance between scanners
         let mut best = 0;
@@ -61,56 +62,288 @@ impl Solver<u64> for Day19Solver {
 }
 
 impl Day19Solver {
-    fn solve(&self) -> Vec<Solve> {
+    fn solve(&self) -> Result<Vec<Solve>, AlignError> {
+        let prints: Vec<Fingerprint> = self.scanners.iter().map(Fingerprint::new).collect();
+
         // track the found scanners (with known relative positions)
         let mut found = vec![Solve {
-            scanner: self.scanners[0].clone(),
+            id: 0,
             offset: (0, 0, 0),
+            rotation: Rotation::identity(),
+            parent: None,
+            overlap: self.scanners[0].beacons.len(),
+            scanner: self.scanners[0].clone(),
         }];
 
         // track the un-found scanners (with unknown positions)
-        let mut not_found: Vec<&Scanner> = self.scanners.iter().skip(1).collect();
-
-        while not_found.len() > 0 {
-            // select an un-found scanner, and attempt to find it
-            let target = not_found.pop().unwrap();
-            let mut added = false;
-
-            'search: for i in 0..found.len() {
-                for mut rotation in target.rotations() {
-                    // for every possible rotation of the target scanner, attempt to fit it to a
-                    // found scanner
-                    if let Some(offset) = found[i].scanner.intersect(&rotation) {
-                        // translate all the points in the rotated target scanner relative to the
-                        // one we fit it to, and mark it as found
-                        rotation.translate(offset);
+        let mut not_found: Vec<usize> = (1..self.scanners.len()).collect();
+
+        // attempt to align every un-found scanner against each found scanner in turn, so that
+        // each pair of scanners is only ever compared once
+        let mut next = 0;
+        while next < found.len() && !not_found.is_empty() {
+            let anchor = next;
+            next += 1;
+
+            let mut remaining = vec![];
+            for target in not_found {
+                let alignment = found[anchor].scanner.align(
+                    &prints[found[anchor].id],
+                    &self.scanners[target],
+                    &prints[target],
+                    self.min_overlap,
+                );
+                match alignment {
+                    Some(Alignment {
+                        rotation,
+                        offset,
+                        overlap,
+                    }) => {
+                        // the anchor is already in the reference frame of scanner 0, so the
+                        // alignment places the target there too
+                        let mut scanner = self.scanners[target].rotate(&rotation);
+                        scanner.translate(offset);
                         found.push(Solve {
-                            scanner: rotation,
+                            id: target,
                             offset,
+                            rotation,
+                            parent: Some(found[anchor].id),
+                            overlap,
+                            scanner,
                         });
-
-                        added = true;
-                        break 'search;
                     }
+                    None => remaining.push(target),
                 }
             }
+            not_found = remaining;
+        }
+
+        // once a full pass over the found scanners makes no progress, the rest can never be
+        // aligned
+        if !not_found.is_empty() {
+            not_found.sort_unstable();
+            return Err(AlignError {
+                unaligned: not_found,
+            });
+        }
+        Ok(found)
+    }
 
-            // it's possible that we can't properly fit the scanner at all, so add it back to the
-            // collection for later
-            if !added {
-                not_found.insert(0, target);
+    fn print_report(&self) {
+        let mut solves = match self.solve() {
+            Ok(solves) => solves,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        solves.sort_by_key(|solve| solve.id);
+        for solve in &solves {
+            let (x, y, z) = solve.offset;
+            print!(
+                "scanner {}: position {},{},{} orientation {}",
+                solve.id, x, y, z, solve.rotation
+            );
+            match solve.parent {
+                Some(parent) => println!(
+                    ", aligned against scanner {} with {} overlapping beacons",
+                    parent, solve.overlap
+                ),
+                None => println!(", reference"),
             }
         }
+    }
+
+    fn export(&self, path: &Path) -> io::Result<()> {
+        let format = path.extension().and_then(|ext| ext.to_str());
+        if !matches!(format, Some("csv" | "xyz" | "ply")) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "export format must be one of csv, xyz or ply",
+            ));
+        }
+
+        let solves = self
+            .solve()
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        let reconstruction = Reconstruction::new(&solves);
 
-        found
+        let mut file = BufWriter::new(File::create(path)?);
+        match format {
+            Some("csv") => reconstruction.write_csv(&mut file)?,
+            Some("xyz") => reconstruction.write_xyz(&mut file)?,
+            _ => reconstruction.write_ply(&mut file)?,
+        }
+        file.flush()
+    }
+}
+
+/// The merged map of every beacon and scanner, in the reference frame of
+/// scanner 0.
+struct Reconstruction {
+    // scanner ids and positions, ordered by id
+    scanners: Vec<(usize, Beacon)>,
+    // unique beacon positions, sorted so that exports can be diffed
+    beacons: Vec<Beacon>,
+}
+
+impl Reconstruction {
+    fn new(solves: &[Solve]) -> Self {
+        let mut scanners: Vec<(usize, Beacon)> = solves
+            .iter()
+            .map(|solve| {
+                let (x, y, z) = solve.offset;
+                (solve.id, Beacon { x, y, z })
+            })
+            .collect();
+        scanners.sort_unstable();
+
+        let beacons: HashSet<Beacon> = solves
+            .iter()
+            .flat_map(|solve| solve.scanner.beacons.iter().cloned())
+            .collect();
+        let mut beacons: Vec<Beacon> = beacons.into_iter().collect();
+        beacons.sort_unstable();
+
+        Self { scanners, beacons }
+    }
+
+    fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "kind,id,x,y,z")?;
+        for (id, Beacon { x, y, z }) in &self.scanners {
+            writeln!(w, "scanner,{},{},{},{}", id, x, y, z)?;
+        }
+        for (id, Beacon { x, y, z }) in self.beacons.iter().enumerate() {
+            writeln!(w, "beacon,{},{},{},{}", id, x, y, z)?;
+        }
+        Ok(())
+    }
+
+    /// Write an XYZ point cloud, with a trailing scalar column holding the
+    /// scanner id, or -1 for beacons.
+    fn write_xyz<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for (id, Beacon { x, y, z }) in &self.scanners {
+            writeln!(w, "{} {} {} {}", x, y, z, id)?;
+        }
+        for Beacon { x, y, z } in &self.beacons {
+            writeln!(w, "{} {} {} -1", x, y, z)?;
+        }
+        Ok(())
+    }
+
+    /// Write an ASCII PLY point cloud, with scanners coloured red and tagged
+    /// with their id, and beacons coloured white and tagged with -1.
+    fn write_ply<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "ply")?;
+        writeln!(w, "format ascii 1.0")?;
+        writeln!(
+            w,
+            "element vertex {}",
+            self.scanners.len() + self.beacons.len()
+        )?;
+        for property in ["int x", "int y", "int z"] {
+            writeln!(w, "property {}", property)?;
+        }
+        for property in ["uchar red", "uchar green", "uchar blue", "int scanner"] {
+            writeln!(w, "property {}", property)?;
+        }
+        writeln!(w, "end_header")?;
+
+        for (id, Beacon { x, y, z }) in &self.scanners {
+            writeln!(w, "{} {} {} 255 0 0 {}", x, y, z, id)?;
+        }
+        for Beacon { x, y, z } in &self.beacons {
+            writeln!(w, "{} {} {} 255 255 255 -1", x, y, z)?;
+        }
+        Ok(())
     }
 }
 
 struct Solve {
+    id: usize,
     offset: (i32, i32, i32),
+    rotation: Rotation,
+    parent: Option<usize>,
+    overlap: usize,
     scanner: Scanner,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct AlignError {
+    unaligned: Vec<usize>,
+}
+
+impl fmt::Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<String> = self.unaligned.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "could not align scanners {} with the rest",
+            ids.join(", ")
+        )
+    }
+}
+
+struct Alignment {
+    rotation: Rotation,
+    offset: (i32, i32, i32),
+    overlap: usize,
+}
+
+/// Rotation and translation invariant summary of a scanner, used to quickly
+/// rule out scanners and beacons that cannot possibly line up.
+struct Fingerprint {
+    // multiset of squared distances between every pair of beacons
+    distances: HashMap<i64, usize>,
+    // sorted squared distances from each beacon to every other beacon
+    beacons: Vec<Vec<i64>>,
+}
+
+impl Fingerprint {
+    fn new(scanner: &Scanner) -> Self {
+        let mut distances = HashMap::new();
+        let mut beacons = vec![vec![]; scanner.beacons.len()];
+        for (i, a) in scanner.beacons.iter().enumerate() {
+            for (j, b) in scanner.beacons.iter().enumerate().skip(i + 1) {
+                let distance = a.distance2(b);
+                *distances.entry(distance).or_insert(0) += 1;
+                beacons[i].push(distance);
+                beacons[j].push(distance);
+            }
+        }
+        for beacon in &mut beacons {
+            beacon.sort_unstable();
+        }
+        Self { distances, beacons }
+    }
+
+    fn shared_distances(&self, other: &Fingerprint) -> usize {
+        self.distances
+            .iter()
+            .map(|(distance, count)| {
+                usize::min(*count, *other.distances.get(distance).unwrap_or(&0))
+            })
+            .sum()
+    }
+
+    fn shared_beacon_distances(&self, i: usize, other: &Fingerprint, j: usize) -> usize {
+        let (a, b) = (&self.beacons[i], &other.beacons[j]);
+        let (mut x, mut y, mut count) = (0, 0, 0);
+        while x < a.len() && y < b.len() {
+            match a[x].cmp(&b[y]) {
+                Ordering::Less => x += 1,
+                Ordering::Greater => y += 1,
+                Ordering::Equal => {
+                    count += 1;
+                    x += 1;
+                    y += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
 #[derive(Clone)]
 struct Scanner {
     beacons: Vec<Beacon>,
@@ -125,107 +358,129 @@ impl Scanner {
         }
     }
 
-    fn intersect(&self, other: &Scanner) -> Option<(i32, i32, i32)> {
+    fn rotate(&self, rotation: &Rotation) -> Scanner {
+        Scanner {
+            beacons: self.beacons.iter().map(|b| rotation.apply(b)).collect(),
+        }
+    }
+
+    fn align(
+        &self,
+        print: &Fingerprint,
+        other: &Scanner,
+        other_print: &Fingerprint,
+        min_overlap: usize,
+    ) -> Option<Alignment> {
+        // if the scanners share enough beacons, they must share all of the distances between
+        // those beacons
+        if print.shared_distances(other_print) < min_overlap * min_overlap.saturating_sub(1) / 2 {
+            return None;
+        }
+
         let beacons: HashSet<Beacon> = self.beacons.iter().cloned().collect();
+        let rotations = Rotation::all();
+        let min_shared = min_overlap.saturating_sub(1);
+
+        // consider each pair of beacons that could be the same, and for each rotation, check how
+        // many other beacons line up if they are
+        for (i, a) in self.beacons.iter().enumerate() {
+            for (j, b) in other.beacons.iter().enumerate() {
+                // a matching beacon shares its distance to every other overlapping beacon
+                if print.shared_beacon_distances(i, other_print, j) < min_shared {
+                    continue;
+                }
 
-        // consider each pair of co-ordinates and assume those two beacons are the same
-        for i in &self.beacons {
-            for j in &other.beacons {
-                let offset = (i.x - j.x, i.y - j.y, i.z - j.z);
-                let mut other = other.clone();
-                other.translate(offset);
-
-                let count = other
-                    .beacons
-                    .iter()
-                    .filter(|beacon| beacons.contains(beacon))
-                    .count();
-                if count >= 12 {
-                    return Some(offset);
+                for rotation in &rotations {
+                    let rotated = rotation.apply(b);
+                    let offset = (a.x - rotated.x, a.y - rotated.y, a.z - rotated.z);
+                    let overlap = other
+                        .beacons
+                        .iter()
+                        .filter(|beacon| {
+                            beacons.contains(&rotation.apply(beacon).translated(offset))
+                        })
+                        .count();
+                    if overlap >= min_overlap {
+                        return Some(Alignment {
+                            rotation: *rotation,
+                            offset,
+                            overlap,
+                        });
+                    }
                 }
             }
         }
 
         None
     }
+}
+
+const DEFAULT_MIN_OVERLAP: usize = 12;
+
+/// A proper rotation, made up of a signed permutation of the axes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Rotation {
+    matrix: [[i32; 3]; 3],
+}
+
+impl Rotation {
+    fn identity() -> Self {
+        Self {
+            matrix: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        }
+    }
+
+    fn all() -> Vec<Rotation> {
+        let permutations = [
+            ([0, 1, 2], 1),
+            ([0, 2, 1], -1),
+            ([1, 0, 2], -1),
+            ([1, 2, 0], 1),
+            ([2, 0, 1], 1),
+            ([2, 1, 0], -1),
+        ];
 
-    fn rotations(&self) -> Vec<Scanner> {
         let mut rotations = vec![];
-        for i in &[-1, 1] {
-            for j in &[-1, 1] {
-                for k in &[-1, 1] {
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * x,
-                                y: j * y,
-                                z: k * z,
-                            })
-                            .collect(),
-                    });
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * x,
-                                y: j * z,
-                                z: k * y,
-                            })
-                            .collect(),
-                    });
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * y,
-                                y: j * x,
-                                z: k * z,
-                            })
-                            .collect(),
-                    });
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * y,
-                                y: j * z,
-                                z: k * x,
-                            })
-                            .collect(),
-                    });
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * z,
-                                y: j * x,
-                                z: k * y,
-                            })
-                            .collect(),
-                    });
-                    rotations.push(Scanner {
-                        beacons: self
-                            .beacons
-                            .iter()
-                            .map(|Beacon { x, y, z }| Beacon {
-                                x: i * z,
-                                y: j * y,
-                                z: k * x,
-                            })
-                            .collect(),
-                    });
+        for (permutation, parity) in permutations {
+            for signs in 0..8 {
+                let signs = [0, 1, 2].map(|axis| if signs & (1 << axis) != 0 { -1 } else { 1 });
+
+                // only keep orientations with a determinant of 1, the rest are reflections
+                if parity * signs.iter().product::<i32>() != 1 {
+                    continue;
+                }
+
+                let mut matrix = [[0; 3]; 3];
+                for row in 0..3 {
+                    matrix[row][permutation[row]] = signs[row];
                 }
+                rotations.push(Rotation { matrix });
             }
         }
-
         rotations
     }
+
+    fn apply(&self, beacon: &Beacon) -> Beacon {
+        let [x, y, z] = self
+            .matrix
+            .map(|row| row[0] * beacon.x + row[1] * beacon.y + row[2] * beacon.z);
+        Beacon { x, y, z }
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let axes: Vec<String> = self
+            .matrix
+            .iter()
+            .map(|row| {
+                let axis = row.iter().position(|n| *n != 0).unwrap();
+                let sign = if row[axis] < 0 { '-' } else { '+' };
+                format!("{}{}", sign, ['x', 'y', 'z'][axis])
+            })
+            .collect();
+        write!(f, "[{}]", axes.join(","))
+    }
 }
 
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Hash, Clone, Copy)]
@@ -235,6 +490,25 @@ struct Beacon {
     z: i32,
 }
 
+impl Beacon {
+    fn translated(&self, (dx, dy, dz): (i32, i32, i32)) -> Beacon {
+        Beacon {
+            x: self.x + dx,
+            y: self.y + dy,
+            z: self.z + dz,
+        }
+    }
+
+    fn distance2(&self, other: &Beacon) -> i64 {
+        let (dx, dy, dz) = (
+            (self.x - other.x) as i64,
+            (self.y - other.y) as i64,
+            (self.z - other.z) as i64,
+        );
+        dx * dx + dy * dy + dz * dz
+    }
+}
+
 #[cfg(test)]
 mod day19tests {
     use super::*;
@@ -392,8 +666,153 @@ mod day19tests {
         let solver = Day19Solver::new(EXAMPLE_DATA.trim());
         assert_eq!(solver.solve2(), Some(3621));
     }
+
+    #[test]
+    fn test_unalignable() {
+        let problem = format!(
+            "{}\n\n--- scanner 5 ---\n0,0,0\n1,2,3\n-400,50,7",
+            EXAMPLE_DATA.trim()
+        );
+        let solver = Day19Solver::new(&problem);
+        assert_eq!(
+            solver.solve().err(),
+            Some(AlignError { unaligned: vec![5] })
+        );
+        assert_eq!(solver.solve1(), None);
+
+        // scanners 0 and 1 only share 12 beacons, so nothing can be aligned to scanner 0
+        let mut solver = Day19Solver::new(EXAMPLE_DATA.trim());
+        solver.min_overlap = 13;
+        assert_eq!(
+            solver.solve().err(),
+            Some(AlignError {
+                unaligned: vec![1, 2, 3, 4]
+            })
+        );
+    }
+
+    #[test]
+    fn test_rotations() {
+        let rotations = Rotation::all();
+        assert_eq!(rotations.len(), 24);
+        assert!(rotations.contains(&Rotation::identity()));
+
+        // every rotation is distinct, and none of them are mirror images
+        let beacon = Beacon { x: 1, y: 2, z: 3 };
+        let rotated: HashSet<Beacon> = rotations.iter().map(|r| r.apply(&beacon)).collect();
+        assert_eq!(rotated.len(), 24);
+        assert!(!rotated.contains(&Beacon { x: -1, y: 2, z: 3 }));
+        assert!(!rotated.contains(&Beacon { x: 2, y: 1, z: 3 }));
+    }
+
+    #[test]
+    fn test_reconstruction() {
+        let solver = Day19Solver::new(EXAMPLE_DATA.trim());
+        let mut solves = solver.solve().unwrap();
+        solves.sort_by_key(|solve| solve.id);
+
+        let offsets: Vec<(i32, i32, i32)> = solves.iter().map(|solve| solve.offset).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                (0, 0, 0),
+                (68, -1246, -43),
+                (1105, -1205, 1229),
+                (-92, -2380, -20),
+                (-20, -1133, 1061),
+            ]
+        );
+
+        let parents: Vec<Option<usize>> = solves.iter().map(|solve| solve.parent).collect();
+        assert_eq!(parents, vec![None, Some(0), Some(4), Some(1), Some(1)]);
+        assert_eq!(solves[1].overlap, 12);
+        assert_eq!(solves[4].overlap, 12);
+
+        // each scanner, once placed, sees enough of the beacons its parent does
+        for solve in &solves[1..] {
+            let parent = &solves[solve.parent.unwrap()];
+            let seen: HashSet<Beacon> = parent.scanner.beacons.iter().copied().collect();
+            let shared = solve
+                .scanner
+                .beacons
+                .iter()
+                .filter(|beacon| seen.contains(beacon))
+                .count();
+            assert!(shared >= solver.min_overlap);
+        }
+
+        // the beacons scanners 0 and 1 share, in scanner 0's frame, from the puzzle
+        let shared = [
+            (-618, -824, -621),
+            (-537, -823, -458),
+            (-447, -329, 318),
+            (404, -588, -901),
+            (544, -627, -890),
+            (528, -643, 409),
+            (-661, -816, -575),
+            (390, -675, -793),
+            (423, -701, 434),
+            (-345, -311, 381),
+            (459, -707, 401),
+            (-485, -357, 347),
+        ];
+        for (x, y, z) in shared {
+            let beacon = Beacon { x, y, z };
+            assert!(solves[0].scanner.beacons.contains(&beacon));
+            assert!(solves[1].scanner.beacons.contains(&beacon));
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let solver = Day19Solver::new(EXAMPLE_DATA.trim());
+        let reconstruction = Reconstruction::new(&solver.solve().unwrap());
+        assert_eq!(reconstruction.scanners.len(), 5);
+        assert_eq!(reconstruction.beacons.len(), 79);
+
+        let mut csv = vec![];
+        reconstruction.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 5 + 79);
+        assert_eq!(lines[0], "kind,id,x,y,z");
+        assert_eq!(lines[2], "scanner,1,68,-1246,-43");
+        assert_eq!(lines[6], "beacon,0,-892,524,684");
+
+        let mut xyz = vec![];
+        reconstruction.write_xyz(&mut xyz).unwrap();
+        let xyz = String::from_utf8(xyz).unwrap();
+        assert_eq!(xyz.lines().count(), 5 + 79);
+        assert_eq!(xyz.lines().nth(3), Some("-92 -2380 -20 3"));
+
+        let mut ply = vec![];
+        reconstruction.write_ply(&mut ply).unwrap();
+        let ply = String::from_utf8(ply).unwrap();
+        assert!(ply.starts_with("ply\nformat ascii 1.0\nelement vertex 84\n"));
+        let body: Vec<&str> = ply
+            .lines()
+            .skip_while(|line| *line != "end_header")
+            .collect();
+        assert_eq!(body.len(), 1 + 5 + 79);
+        assert_eq!(body[1], "0 0 0 255 0 0 0");
+    }
 }
 
 fn main() {
-    solve_file::<Day19Solver, u64>("day19.txt");
+    let overlap = std::env::args().find_map(|arg| {
+        arg.strip_prefix("--overlap=")
+            .map(|overlap| overlap.parse().unwrap())
+    });
+    let export = std::env::args().find_map(|arg| arg.strip_prefix("--export=").map(String::from));
+
+    if std::env::args().any(|arg| arg == "--report") || overlap.is_some() || export.is_some() {
+        let mut solver = Day19Solver::new(&load_file("day19.txt"));
+        solver.min_overlap = overlap.unwrap_or(DEFAULT_MIN_OVERLAP);
+        match export {
+            Some(path) => solver.export(Path::new(&path)).unwrap(),
+            None => solver.print_report(),
+        }
+    } else {
+        solve_file::<Day19Solver, u64>("day19.txt");
+    }
 }
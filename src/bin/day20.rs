@@ -1,13 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::iter;
+use std::path::Path;
 
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
 
 struct Day20Solver {
-    table: Vec<Pixel>,
+    rule: Rule,
     image: Image,
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Pixel {
     Light,
     Dark,
@@ -15,6 +19,22 @@ enum Pixel {
 
 impl Solver<u64> for Day20Solver {
     fn new(problem: &str) -> Self {
+        Self::with_kernel(problem, Kernel::square(1)).unwrap()
+    }
+
+    fn solve1(&self) -> Option<u64> {
+        let image = (0..2).fold(self.image.clone(), |image, _| image.transform(&self.rule));
+        image.lit().map(|lit| lit as u64)
+    }
+
+    fn solve2(&self) -> Option<u64> {
+        let image = (0..50).fold(self.image.clone(), |image, _| image.transform(&self.rule));
+        image.lit().map(|lit| lit as u64)
+    }
+}
+
+impl Day20Solver {
+    fn with_kernel(problem: &str, kernel: Kernel) -> Result<Self, RuleError> {
         let (table, image) = problem.split_once("\n\n").unwrap();
         let table = table
             .chars()
@@ -25,98 +45,408 @@ impl Solver<u64> for Day20Solver {
                 _ => unreachable!(),
             })
             .collect();
+        let rule = Rule::new(kernel, table)?;
         let image = Image::parse(image);
-        Self { table, image }
+        Ok(Self { rule, image })
     }
 
-    fn solve1(&self) -> Option<u64> {
-        let image = (0..2).fold(self.image.clone(), |image, _| image.transform(&self.table));
-        Some(image.pixels.len() as u64)
+    /// Every generation of the image, starting from the input.
+    fn generations(&self) -> impl Iterator<Item = Image> + '_ {
+        iter::successors(Some(self.image.clone()), move |image| {
+            Some(image.transform(&self.rule))
+        })
     }
 
-    fn solve2(&self) -> Option<u64> {
-        let image = (0..50).fold(self.image.clone(), |image, _| image.transform(&self.table));
-        Some(image.pixels.len() as u64)
+    /// Write the first `steps` generations as numbered PBM frames in `dir`.
+    /// Every frame is cropped to the window of the final generation, so that
+    /// the background around the earlier frames is visible too.
+    fn export_frames(&self, dir: &Path, steps: usize) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let grow = steps as isize * self.rule.kernel.radius();
+        let (x1, y1) = self.image.top_left;
+        let (x2, y2) = self.image.bottom_right;
+        let (top_left, bottom_right) = ((x1 - grow, y1 - grow), (x2 + grow, y2 + grow));
+
+        for (i, image) in self.generations().take(steps + 1).enumerate() {
+            let path = dir.join(format!("frame-{:03}.pbm", i));
+            let mut file = BufWriter::new(File::create(path)?);
+            image.write_pbm(&mut file, top_left, bottom_right)?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// the largest kernel whose lookup table is still reasonable to hold in memory
+const MAX_KERNEL_SIZE: usize = 25;
+
+#[derive(Debug, PartialEq, Eq)]
+enum RuleError {
+    KernelTooLarge(usize),
+    DuplicateOffset((isize, isize)),
+    TableSize { expected: usize, found: usize },
+    NoCentre,
+    InvalidSpec(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::KernelTooLarge(size) => write!(
+                f,
+                "kernel has {} cells, but at most {} are supported",
+                size, MAX_KERNEL_SIZE
+            ),
+            RuleError::DuplicateOffset((x, y)) => {
+                write!(f, "kernel contains offset ({}, {}) twice", x, y)
+            }
+            RuleError::TableSize { expected, found } => write!(
+                f,
+                "kernel needs a table of {} entries, but found {}",
+                expected, found
+            ),
+            RuleError::NoCentre => write!(f, "kernel does not contain its centre"),
+            RuleError::InvalidSpec(spec) => write!(f, "invalid specification {:?}", spec),
+        }
+    }
+}
+
+/// The neighbourhood of a pixel, as a list of offsets whose values are read in
+/// order, most significant bit first, to form a key into a rule table.
+#[derive(Debug, Clone, PartialEq)]
+struct Kernel {
+    offsets: Vec<(isize, isize)>,
+    // radius of the kernel, if it is a full square read row by row
+    square: Option<isize>,
+}
+
+impl Kernel {
+    fn new(offsets: Vec<(isize, isize)>) -> Result<Self, RuleError> {
+        if offsets.len() > MAX_KERNEL_SIZE {
+            return Err(RuleError::KernelTooLarge(offsets.len()));
+        }
+        for (i, offset) in offsets.iter().enumerate() {
+            if offsets[..i].contains(offset) {
+                return Err(RuleError::DuplicateOffset(*offset));
+            }
+        }
+        Ok(Self {
+            offsets,
+            square: None,
+        })
+    }
+
+    /// A (2r+1)x(2r+1) square, such as the 3x3 kernel from the puzzle.
+    fn square(radius: isize) -> Self {
+        debug_assert!(radius >= 0);
+        let offsets = (-radius..=radius)
+            .flat_map(|i| (-radius..=radius).map(move |j| (i, j)))
+            .collect();
+        Self {
+            offsets,
+            square: Some(radius),
+        }
+    }
+
+    /// Every cell within a manhattan distance of r.
+    fn von_neumann(radius: isize) -> Self {
+        debug_assert!(radius >= 0);
+        let offsets = (-radius..=radius)
+            .flat_map(|i| (-radius..=radius).map(move |j| (i, j)))
+            .filter(|(i, j)| i.abs() + j.abs() <= radius)
+            .collect();
+        Self {
+            offsets,
+            square: None,
+        }
+    }
+
+    /// A cell and its six neighbours on a hexagonal grid, using axial
+    /// co-ordinates where each row is skewed half a cell from the last.
+    fn hexagonal() -> Self {
+        Self {
+            offsets: vec![(-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0)],
+            square: None,
+        }
+    }
+
+    /// Parse a kernel from either a named shape (`square:R`, `von-neumann:R`
+    /// or `hexagonal`), or a space separated list of `i,j` offsets.
+    fn parse(spec: &str) -> Result<Self, RuleError> {
+        let invalid = || RuleError::InvalidSpec(spec.to_string());
+        let number = |n: &str| n.parse::<isize>().map_err(|_| invalid());
+        let radius = |r: &str| number(r).and_then(|r| if r < 0 { Err(invalid()) } else { Ok(r) });
+
+        let kernel = match spec.split_once(':') {
+            Some(("square", r)) => Kernel::square(radius(r)?),
+            Some(("von-neumann", r)) => Kernel::von_neumann(radius(r)?),
+            None if spec == "hexagonal" => Kernel::hexagonal(),
+            _ => {
+                let offsets = spec
+                    .split_whitespace()
+                    .map(|offset| {
+                        let (i, j) = offset.split_once(',').ok_or_else(invalid)?;
+                        Ok((number(i)?, number(j)?))
+                    })
+                    .collect::<Result<_, _>>()?;
+                return Kernel::new(offsets);
+            }
+        };
+
+        // named shapes still need to fit in a table
+        if kernel.offsets.len() > MAX_KERNEL_SIZE {
+            return Err(RuleError::KernelTooLarge(kernel.offsets.len()));
+        }
+        Ok(kernel)
+    }
+
+    fn radius(&self) -> isize {
+        self.offsets
+            .iter()
+            .map(|(i, j)| i.abs().max(j.abs()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn table_size(&self) -> usize {
+        1 << self.offsets.len()
+    }
+}
+
+/// A lookup table from every possible kernel key to the next pixel value.
+#[derive(Debug, Clone)]
+struct Rule {
+    kernel: Kernel,
+    table: Vec<Pixel>,
+}
+
+impl Rule {
+    fn new(kernel: Kernel, table: Vec<Pixel>) -> Result<Self, RuleError> {
+        if table.len() != kernel.table_size() {
+            return Err(RuleError::TableSize {
+                expected: kernel.table_size(),
+                found: table.len(),
+            });
+        }
+        Ok(Self { kernel, table })
+    }
+
+    /// Build a rule where the next value depends only on the centre pixel and
+    /// the number of lit pixels around it, like the game of life (which is
+    /// `outer_totalistic(Kernel::square(1), &[3], &[2, 3])`).
+    fn outer_totalistic(kernel: Kernel, birth: &[u32], survive: &[u32]) -> Result<Self, RuleError> {
+        let centre = kernel
+            .offsets
+            .iter()
+            .position(|offset| *offset == (0, 0))
+            .ok_or(RuleError::NoCentre)?;
+        let centre = 1 << (kernel.offsets.len() - 1 - centre);
+
+        let table = (0..kernel.table_size())
+            .map(|key: usize| {
+                let neighbours = (key & !centre).count_ones();
+                let alive = if key & centre != 0 {
+                    survive.contains(&neighbours)
+                } else {
+                    birth.contains(&neighbours)
+                };
+                if alive {
+                    Pixel::Light
+                } else {
+                    Pixel::Dark
+                }
+            })
+            .collect();
+        Rule::new(kernel, table)
+    }
+
+    /// Parse an outer totalistic rule in `B3/S23` notation.
+    fn parse_totalistic(kernel: Kernel, spec: &str) -> Result<Self, RuleError> {
+        let invalid = || RuleError::InvalidSpec(spec.to_string());
+        let counts = |counts: &str| {
+            counts
+                .chars()
+                .map(|ch| ch.to_digit(10).ok_or_else(invalid))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let (birth, survive) = spec.split_once('/').ok_or_else(invalid)?;
+        let birth = counts(birth.strip_prefix('B').ok_or_else(invalid)?)?;
+        let survive = counts(survive.strip_prefix('S').ok_or_else(invalid)?)?;
+        Rule::outer_totalistic(kernel, &birth, &survive)
     }
 }
 
+/// A finite window of lit and unlit pixels, bit-packed row by row, on top of
+/// an infinite background of a single pixel type.
 #[derive(Clone)]
 struct Image {
-    pixels: HashSet<(isize, isize)>,
-    pixel_type: Pixel,
+    bits: Vec<u64>,
+    stride: usize,
+    background: Pixel,
     top_left: (isize, isize),
     bottom_right: (isize, isize),
 }
 
 impl Image {
+    fn new(top_left: (isize, isize), bottom_right: (isize, isize), background: Pixel) -> Self {
+        let height = (bottom_right.0 - top_left.0 + 1) as usize;
+        let width = (bottom_right.1 - top_left.1 + 1) as usize;
+        let stride = width.div_ceil(64);
+        Self {
+            bits: vec![0; height * stride],
+            stride,
+            background,
+            top_left,
+            bottom_right,
+        }
+    }
+
     fn parse(data: &str) -> Self {
-        let lines: Vec<_> = data.split("\n").collect();
-        let pixels = lines
-            .iter()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.chars()
-                    .enumerate()
-                    .filter_map(move |(j, ch)| match ch {
-                        '#' => Some((i as isize, j as isize)),
-                        '.' => None,
-                        _ => unreachable!(),
-                    })
-            })
-            .collect();
+        let lines: Vec<_> = data.split('\n').collect();
+        let mut image = Image::new(
+            (0, 0),
+            (lines.len() as isize - 1, lines[0].len() as isize - 1),
+            Pixel::Dark,
+        );
+        for (i, line) in lines.iter().enumerate() {
+            for (j, ch) in line.chars().enumerate() {
+                match ch {
+                    '#' => image.set(i as isize, j as isize),
+                    '.' => {}
+                    _ => unreachable!(),
+                }
+            }
+        }
+        image
+    }
 
-        Self {
-            pixels,
-            pixel_type: Pixel::Light,
-            top_left: (0, 0),
-            bottom_right: (lines.len() as isize - 1, lines[0].len() as isize - 1),
+    fn contains(&self, x: isize, y: isize) -> bool {
+        x >= self.top_left.0
+            && x <= self.bottom_right.0
+            && y >= self.top_left.1
+            && y <= self.bottom_right.1
+    }
+
+    fn get(&self, x: isize, y: isize) -> bool {
+        if !self.contains(x, y) {
+            return self.background == Pixel::Light;
         }
+        let i = (x - self.top_left.0) as usize;
+        let j = (y - self.top_left.1) as usize;
+        self.bits[i * self.stride + j / 64] >> (j % 64) & 1 != 0
+    }
+
+    fn set(&mut self, x: isize, y: isize) {
+        let i = (x - self.top_left.0) as usize;
+        let j = (y - self.top_left.1) as usize;
+        self.bits[i * self.stride + j / 64] |= 1 << (j % 64);
     }
 
-    fn transform(&self, table: &[Pixel]) -> Self {
-        let mut next = HashSet::new();
-        let next_type = if self.pixel_type == Pixel::Light && table[0] == Pixel::Light {
-            Pixel::Dark
-        } else if self.pixel_type == Pixel::Dark && table[table.len() - 1] == Pixel::Dark {
-            Pixel::Light
-        } else {
-            self.pixel_type
+    /// The number of lit pixels, or None if the background is lit and so
+    /// there are infinitely many.
+    fn lit(&self) -> Option<usize> {
+        match self.background {
+            Pixel::Light => None,
+            Pixel::Dark => Some(
+                self.bits
+                    .iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum(),
+            ),
+        }
+    }
+
+    /// Write the region between two corners (inclusive) as a binary PBM
+    /// bitmap, with lit pixels drawn in black.
+    fn write_pbm<W: Write>(
+        &self,
+        w: &mut W,
+        top_left: (isize, isize),
+        bottom_right: (isize, isize),
+    ) -> io::Result<()> {
+        let (x1, y1) = top_left;
+        let (x2, y2) = bottom_right;
+        write!(w, "P4\n{} {}\n", y2 - y1 + 1, x2 - x1 + 1)?;
+
+        for x in x1..=x2 {
+            // each row is packed most significant bit first, and padded to a whole byte
+            let mut row = vec![0u8; ((y2 - y1 + 1) as usize).div_ceil(8)];
+            for (i, y) in (y1..=y2).enumerate() {
+                if self.get(x, y) {
+                    row[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+            w.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    fn transform(&self, rule: &Rule) -> Self {
+        // every background pixel sees only background pixels, so the whole background maps to a
+        // single entry in the table
+        let next_background = match self.background {
+            Pixel::Light => rule.table[rule.table.len() - 1],
+            Pixel::Dark => rule.table[0],
         };
 
+        // only pixels within reach of the kernel of the current window can see a non-background
+        // pixel
+        let r = rule.kernel.radius();
         let (x1, y1) = self.top_left;
         let (x2, y2) = self.bottom_right;
-        for x in x1 - 2..=x2 + 2 {
-            for y in y1 - 2..=y2 + 2 {
-                let mut key = 0;
-                for i in -1..=1 {
-                    for j in -1..=1 {
-                        let is_light = match self.pixel_type {
-                            Pixel::Light => self.pixels.contains(&(x + i, y + j)),
-                            Pixel::Dark => !self.pixels.contains(&(x + i, y + j)),
-                        };
-
-                        key <<= 1;
-                        if is_light {
-                            key |= 1;
+        let mut next = Image::new((x1 - r, y1 - r), (x2 + r, y2 + r), next_background);
+
+        for x in x1 - r..=x2 + r {
+            match rule.kernel.square {
+                Some(r) => {
+                    // roll a square window along the row, shifting out the left column and
+                    // shifting in the right column at each step
+                    let side = (2 * r + 1) as usize;
+                    let column = |y| {
+                        (0..side).fold(0, |acc, i| {
+                            acc | (self.get(x - r + i as isize, y) as usize)
+                                << ((side - 1 - i) * side)
+                        })
+                    };
+                    let keep = (rule.table.len() - 1) & !column_mask(side);
+
+                    let mut key = 0;
+                    for y in y1 - 2 * r..y1 {
+                        key = (key << 1) & keep | column(y);
+                    }
+                    for y in y1 - r..=y2 + r {
+                        key = (key << 1) & keep | column(y + r);
+                        if rule.table[key] == Pixel::Light {
+                            next.set(x, y);
                         }
                     }
                 }
-
-                if table[key] == next_type {
-                    next.insert((x, y));
+                None => {
+                    for y in y1 - r..=y2 + r {
+                        let key = rule
+                            .kernel
+                            .offsets
+                            .iter()
+                            .fold(0, |acc, (i, j)| acc << 1 | self.get(x + i, y + j) as usize);
+                        if rule.table[key] == Pixel::Light {
+                            next.set(x, y);
+                        }
+                    }
                 }
             }
         }
 
-        Self {
-            pixels: next,
-            pixel_type: next_type,
-            top_left: (x1 - 2, y1 - 2),
-            bottom_right: (x2 + 2, y2 + 2),
-        }
+        next
     }
 }
 
+// the bits of a square kernel key that hold its rightmost column
+fn column_mask(side: usize) -> usize {
+    (0..side).fold(0, |acc, i| acc | 1 << (i * side))
+}
+
 #[cfg(test)]
 mod day20tests {
     use super::*;
@@ -151,8 +481,245 @@ mod day20tests {
         let solver = Day20Solver::new(EXAMPLE_DATA.trim());
         assert_eq!(solver.solve2(), Some(3351));
     }
+
+    #[test]
+    fn test_parse() {
+        let solver = Day20Solver::new(EXAMPLE_DATA.trim());
+        assert_eq!(solver.rule.table.len(), 512);
+        assert_eq!(solver.image.lit(), Some(10));
+        assert!(solver.image.get(0, 0));
+        assert!(!solver.image.get(0, 1));
+        assert!(solver.image.get(4, 4));
+        assert!(!solver.image.get(-1, 0));
+    }
+
+    #[test]
+    fn test_background_flip() {
+        // invert the centre pixel, so the infinite background flips every step
+        let table: Vec<Pixel> = (0..512)
+            .map(|key| {
+                if key & 0b000_010_000 != 0 {
+                    Pixel::Dark
+                } else {
+                    Pixel::Light
+                }
+            })
+            .collect();
+        let rule = Rule::new(Kernel::square(1), table).unwrap();
+        let solver = Day20Solver::new(EXAMPLE_DATA.trim());
+
+        let once = solver.image.transform(&rule);
+        assert_eq!(once.lit(), None);
+        assert!(!once.get(0, 0));
+        assert!(once.get(0, 1));
+        assert!(once.get(-100, 100));
+
+        let twice = once.transform(&rule);
+        assert_eq!(twice.lit(), Some(10));
+        assert!(twice.get(0, 0));
+        assert!(!twice.get(-100, 100));
+    }
+
+    #[test]
+    fn test_rule_validation() {
+        assert_eq!(
+            Rule::new(Kernel::square(1), vec![Pixel::Dark; 511]).err(),
+            Some(RuleError::TableSize {
+                expected: 512,
+                found: 511
+            })
+        );
+        assert_eq!(Kernel::hexagonal().table_size(), 128);
+        assert_eq!(Kernel::von_neumann(1).table_size(), 32);
+        assert_eq!(Kernel::square(2).table_size(), 1 << 25);
+        assert_eq!(
+            Kernel::new(vec![(0, 0), (0, 1), (0, 0)]).err(),
+            Some(RuleError::DuplicateOffset((0, 0)))
+        );
+        assert_eq!(
+            Kernel::new(Kernel::square(3).offsets).err(),
+            Some(RuleError::KernelTooLarge(49))
+        );
+        assert_eq!(
+            Rule::outer_totalistic(Kernel::new(vec![(0, 1)]).unwrap(), &[1], &[]).err(),
+            Some(RuleError::NoCentre)
+        );
+    }
+
+    fn lit_pixels(image: &Image) -> Vec<(isize, isize)> {
+        let (x1, y1) = image.top_left;
+        let (x2, y2) = image.bottom_right;
+        (x1..=x2)
+            .flat_map(|x| (y1..=y2).map(move |y| (x, y)))
+            .filter(|(x, y)| image.get(*x, *y))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(Kernel::parse("square:1"), Ok(Kernel::square(1)));
+        assert_eq!(Kernel::parse("von-neumann:2"), Ok(Kernel::von_neumann(2)));
+        assert_eq!(Kernel::parse("hexagonal"), Ok(Kernel::hexagonal()));
+        assert_eq!(
+            Kernel::parse("-1,0 0,0 1,0").map(|kernel| kernel.offsets),
+            Ok(vec![(-1, 0), (0, 0), (1, 0)])
+        );
+        assert_eq!(
+            Kernel::parse("square:3"),
+            Err(RuleError::KernelTooLarge(49))
+        );
+        assert_eq!(
+            Kernel::parse("circle:2"),
+            Err(RuleError::InvalidSpec("circle:2".to_string()))
+        );
+        assert_eq!(
+            Kernel::parse("square:-1"),
+            Err(RuleError::InvalidSpec("square:-1".to_string()))
+        );
+        assert_eq!(
+            Kernel::parse("von-neumann:-1"),
+            Err(RuleError::InvalidSpec("von-neumann:-1".to_string()))
+        );
+
+        let life = Rule::parse_totalistic(Kernel::square(1), "B3/S23").unwrap();
+        let expected = Rule::outer_totalistic(Kernel::square(1), &[3], &[2, 3]).unwrap();
+        assert_eq!(life.table, expected.table);
+        assert!(Rule::parse_totalistic(Kernel::square(1), "B3S23").is_err());
+        assert!(Rule::parse_totalistic(Kernel::square(1), "B3/Sx").is_err());
+    }
+
+    #[test]
+    fn test_write_pbm() {
+        let image = Image::parse("#..#.\n#....\n##..#\n..#..\n..###");
+        let mut pbm = vec![];
+        image
+            .write_pbm(&mut pbm, image.top_left, image.bottom_right)
+            .unwrap();
+        assert_eq!(
+            pbm,
+            [
+                b"P4\n5 5\n".to_vec(),
+                vec![
+                    0b1001_0000,
+                    0b1000_0000,
+                    0b1100_1000,
+                    0b0010_0000,
+                    0b0011_1000
+                ],
+            ]
+            .concat()
+        );
+
+        // regions outside the window are drawn as background
+        let mut pbm = vec![];
+        image.write_pbm(&mut pbm, (-1, -1), (0, 9)).unwrap();
+        assert_eq!(
+            pbm,
+            [b"P4\n11 2\n".to_vec(), vec![0, 0, 0b0100_1000, 0]].concat()
+        );
+    }
+
+    #[test]
+    fn test_generations() {
+        let solver = Day20Solver::new(EXAMPLE_DATA.trim());
+        let lit: Vec<Option<usize>> = solver
+            .generations()
+            .take(3)
+            .map(|image| image.lit())
+            .collect();
+        assert_eq!(lit, vec![Some(10), Some(24), Some(35)]);
+    }
+
+    #[test]
+    fn test_life() {
+        let life = Rule::outer_totalistic(Kernel::square(1), &[3], &[2, 3]).unwrap();
+        let blinker = Image::parse(".#.\n.#.\n.#.");
+        let next = blinker.transform(&life);
+        assert_eq!(lit_pixels(&next), vec![(1, 0), (1, 1), (1, 2)]);
+        let next = next.transform(&life);
+        assert_eq!(lit_pixels(&next), vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn test_kernels_agree() {
+        // the same rule through the rolling square path and the general offset path
+        let solver = Day20Solver::new(EXAMPLE_DATA.trim());
+        let kernel = Kernel::new(Kernel::square(1).offsets).unwrap();
+        let general = Rule::new(kernel, solver.rule.table.clone()).unwrap();
+        let a = solver.image.transform(&solver.rule).transform(&solver.rule);
+        let b = solver.image.transform(&general).transform(&general);
+        assert_eq!(lit_pixels(&a), lit_pixels(&b));
+
+        // a 5x5 square, where a pixel is lit if any pixel within 2 is lit
+        let mut table = vec![Pixel::Light; 1 << 25];
+        table[0] = Pixel::Dark;
+        let rule = Rule::new(Kernel::square(2), table).unwrap();
+        let dot = Image::parse("#");
+        let grown = dot.transform(&rule);
+        assert_eq!(grown.top_left, (-2, -2));
+        assert_eq!(grown.lit(), Some(25));
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        // a pixel is lit if any of its neighbours (or itself) were lit
+        let any = |kernel: Kernel| {
+            let mut table = vec![Pixel::Light; kernel.table_size()];
+            table[0] = Pixel::Dark;
+            Rule::new(kernel, table).unwrap()
+        };
+        let dot = Image::parse("#");
+        assert_eq!(dot.transform(&any(Kernel::von_neumann(1))).lit(), Some(5));
+        assert_eq!(dot.transform(&any(Kernel::von_neumann(2))).lit(), Some(13));
+        assert_eq!(dot.transform(&any(Kernel::hexagonal())).lit(), Some(7));
+    }
 }
 
 fn main() {
-    solve_file::<Day20Solver, u64>("day20.txt");
+    let arg = |prefix| std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(String::from));
+    let kernel = arg("--kernel=");
+    let totalistic = arg("--rule=");
+    let frames = arg("--frames=");
+    if kernel.is_none() && totalistic.is_none() && frames.is_none() {
+        solve_file::<Day20Solver, u64>("day20.txt");
+        return;
+    }
+
+    let build = || -> Result<Day20Solver, RuleError> {
+        let kernel = match kernel {
+            Some(spec) => Kernel::parse(&spec)?,
+            None => Kernel::square(1),
+        };
+        let problem = load_file("day20.txt");
+        match totalistic {
+            // replace the table from the input entirely
+            Some(spec) => {
+                let rule = Rule::parse_totalistic(kernel, &spec)?;
+                let (_, image) = problem.split_once("\n\n").unwrap();
+                let image = Image::parse(image);
+                Ok(Day20Solver { rule, image })
+            }
+            None => Day20Solver::with_kernel(&problem, kernel),
+        }
+    };
+    let solver = match build() {
+        Ok(solver) => solver,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    if let Some(dir) = frames {
+        let steps = arg("--steps=").map_or(50, |steps| steps.parse().unwrap());
+        solver.export_frames(Path::new(&dir), steps).unwrap();
+        return;
+    }
+
+    for (part, result) in [(1, solver.solve1()), (2, solver.solve2())] {
+        match result {
+            Some(result) => println!("part {}: {}", part, result),
+            None => println!("part {}: infinite", part),
+        }
+    }
 }
@@ -1,174 +1,299 @@
-use itertools::Itertools;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
 
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
 
 struct Day22Solver {
-    cuboids: Vec<Cuboid>,
+    axes: Vec<String>,
+    steps: Vec<Step>,
 }
 
 impl Solver<u64> for Day22Solver {
     fn new(problem: &str) -> Self {
+        let mut axes: Option<Vec<String>> = None;
+        let mut steps = vec![];
+        for line in problem.split('\n') {
+            let (names, step) = Step::parse(line).unwrap();
+            match &axes {
+                Some(axes) => assert_eq!(axes, &names, "every step must use the same axes"),
+                None => axes = Some(names.iter().map(|name| name.to_string()).collect()),
+            }
+            steps.push(step);
+        }
+
         Self {
-            cuboids: problem.split("\n").map(Cuboid::parse).collect(),
+            axes: axes.unwrap(),
+            steps,
         }
     }
 
     fn solve1(&self) -> Option<u64> {
-        let candidates: Vec<Cuboid> = self
-            .cuboids
-            .iter()
-            .filter(|c| {
-                let coords = [c.x_min, c.x_max, c.y_min, c.y_max, c.z_min, c.z_max];
-                for coord in coords {
-                    if coord < -50 || coord > 50 {
-                        return false;
-                    }
-                }
-                true
-            })
-            .cloned()
-            .collect();
-        return Some(solve(&candidates));
-
-        let mut space = [[[false; 101]; 101]; 101];
-        for cuboid in candidates {
-            for x in cuboid.x_min..=cuboid.x_max {
-                for y in cuboid.y_min..=cuboid.y_max {
-                    for z in cuboid.z_min..=cuboid.z_max {
-                        space[(x + 50) as usize][(y + 50) as usize][(z + 50) as usize] =
-                            cuboid.toggle;
-                    }
-                }
-            }
+        let region = Cuboid {
+            bounds: vec![(-50, 50); self.axes.len()],
+        };
+        self.lit_within(&region)
+    }
+
+    fn solve2(&self) -> Option<u64> {
+        let mut reactor = Reactor::new();
+        for step in &self.steps {
+            reactor.apply(step);
         }
+        u64::try_from(reactor.volume()?).ok()
+    }
+}
 
-        let mut count = 0;
-        for x in 0..101 {
-            for y in 0..101 {
-                for z in 0..101 {
-                    if space[x][y][z] {
-                        count += 1;
-                    }
-                }
+impl Day22Solver {
+    /// Count the cubes left on inside a region, only tracking the parts of
+    /// each step that fall within it.
+    fn lit_within(&self, region: &Cuboid) -> Option<u64> {
+        let mut reactor = Reactor::new();
+        for step in &self.steps {
+            if let Some(cuboid) = step.cuboid.intersect(region) {
+                reactor.apply(&Step {
+                    toggle: step.toggle,
+                    cuboid,
+                });
             }
         }
-
-        Some(count)
+        u64::try_from(reactor.volume()?).ok()
     }
 
-    fn solve2(&self) -> Option<u64> {
-        Some(solve(&self.cuboids))
+    /// Parse a query region, which may name the axes in any order, and leaves
+    /// any axes it doesn't name unbounded.
+    fn region(&self, data: &str) -> Option<Cuboid> {
+        let (names, cuboid) = Cuboid::parse(data)?;
+        let mut bounds = vec![(i64::MIN, i64::MAX); self.axes.len()];
+        for (name, range) in names.iter().zip(cuboid.bounds) {
+            let axis = self.axes.iter().position(|axis| axis == name)?;
+            bounds[axis] = range;
+        }
+        Some(Cuboid { bounds })
     }
 }
 
-fn solve(cuboids: &[Cuboid]) -> u64 {
-    let xspace: Vec<i32> = cuboids
-        .iter()
-        .flat_map(|cuboid| [cuboid.x_min, cuboid.x_max + 1])
-        .sorted()
-        .dedup()
-        .collect();
-    let yspace: Vec<i32> = cuboids
-        .iter()
-        .flat_map(|cuboid| [cuboid.y_min, cuboid.y_max + 1])
-        .sorted()
-        .dedup()
-        .collect();
-    let zspace: Vec<i32> = cuboids
-        .iter()
-        .flat_map(|cuboid| [cuboid.z_min, cuboid.z_max + 1])
-        .sorted()
-        .dedup()
-        .collect();
-    let mut space = vec![vec![vec![false; zspace.len()]; yspace.len()]; xspace.len()];
-
-    for cuboid in cuboids {
-        let xs: Vec<(usize, i32)> = xspace
-            .iter()
-            .cloned()
-            .enumerate()
-            .skip_while(|(_, x)| *x < cuboid.x_min)
-            .take_while(|(_, x)| *x < cuboid.x_max + 1)
-            .collect();
-        let ys: Vec<(usize, i32)> = yspace
-            .iter()
-            .cloned()
-            .enumerate()
-            .skip_while(|(_, y)| *y < cuboid.y_min)
-            .take_while(|(_, y)| *y < cuboid.y_max + 1)
-            .collect();
-        let zs: Vec<(usize, i32)> = zspace
-            .iter()
-            .cloned()
-            .enumerate()
-            .skip_while(|(_, z)| *z < cuboid.z_min)
-            .take_while(|(_, z)| *z < cuboid.z_max + 1)
-            .collect();
+/// The state of the reactor, as a signed sum of cuboid volumes.
+///
+/// Turning on a cuboid adds it, and every step subtracts its overlap with the
+/// existing cuboids (by adding each overlap with the opposite sign), so that
+/// no cube is ever counted twice.
+struct Reactor {
+    regions: HashMap<Cuboid, i128>,
+    history: Vec<Step>,
+}
 
-        for (x, _) in &xs {
-            for (y, _) in &ys {
-                for (z, _) in &zs {
-                    space[*x][*y][*z] = cuboid.toggle;
+impl Reactor {
+    fn new() -> Self {
+        Self {
+            regions: HashMap::new(),
+            history: vec![],
+        }
+    }
+
+    fn apply(&mut self, step: &Step) {
+        let mut changes: HashMap<Cuboid, i128> = HashMap::new();
+        for (region, sign) in &self.regions {
+            if let Some(overlap) = region.intersect(&step.cuboid) {
+                *changes.entry(overlap).or_insert(0) -= *sign;
+            }
+        }
+        if step.toggle {
+            *changes.entry(step.cuboid.clone()).or_insert(0) += 1;
+        }
+
+        for (region, change) in changes {
+            match self.regions.entry(region) {
+                Entry::Occupied(mut entry) => {
+                    *entry.get_mut() += change;
+                    if *entry.get() == 0 {
+                        entry.remove();
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    if change != 0 {
+                        entry.insert(change);
+                    }
                 }
             }
         }
+        self.history.push(step.clone());
+    }
+
+    /// Find the index of the last step applied that covered a point, and
+    /// whether it left the point on.
+    fn last_set(&self, point: &[i64]) -> Option<(usize, bool)> {
+        self.history
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, step)| step.cuboid.contains(point))
+            .map(|(i, step)| (i, step.toggle))
     }
 
-    let mut count = 0;
-    for (x, xdiff) in xspace.iter().zip(xspace.iter().skip(1)).enumerate() {
-        for (y, ydiff) in yspace.iter().zip(yspace.iter().skip(1)).enumerate() {
-            for (z, zdiff) in zspace.iter().zip(zspace.iter().skip(1)).enumerate() {
-                if space[x][y][z] {
-                    let xdiff = (xdiff.1 - xdiff.0) as u64;
-                    let ydiff = (ydiff.1 - ydiff.0) as u64;
-                    let zdiff = (zdiff.1 - zdiff.0) as u64;
-                    count += xdiff * ydiff * zdiff;
+    /// The number of cubes on, or None if it's too large to count.
+    fn volume(&self) -> Option<i128> {
+        self.regions
+            .iter()
+            .try_fold(0i128, |total, (region, sign)| {
+                total.checked_add(sign.checked_mul(region.volume()?)?)
+            })
+    }
+
+    fn volume_within(&self, query: &Cuboid) -> Option<i128> {
+        self.regions
+            .iter()
+            .try_fold(0i128, |total, (region, sign)| {
+                match region.intersect(query) {
+                    Some(overlap) => total.checked_add(sign.checked_mul(overlap.volume()?)?),
+                    None => Some(total),
                 }
+            })
+    }
+}
+
+/// An axis-aligned box in any number of dimensions, with inclusive bounds.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Cuboid {
+    bounds: Vec<(i64, i64)>,
+}
+
+impl Cuboid {
+    /// Parse a comma separated list of `name=min..max` ranges, returning the
+    /// names of the axes alongside the cuboid.
+    fn parse(data: &str) -> Option<(Vec<&str>, Cuboid)> {
+        let mut names = vec![];
+        let mut bounds = vec![];
+        for range in data.split(',') {
+            let (name, range) = range.trim().split_once('=')?;
+            let (min, max) = range.split_once("..")?;
+            let (min, max): (i64, i64) = (min.parse().ok()?, max.parse().ok()?);
+            if min > max {
+                return None;
             }
+            names.push(name);
+            bounds.push((min, max));
         }
+        Some((names, Cuboid { bounds }))
+    }
+
+    fn contains(&self, point: &[i64]) -> bool {
+        point.len() == self.bounds.len()
+            && self
+                .bounds
+                .iter()
+                .zip(point)
+                .all(|((min, max), n)| (min..=max).contains(&n))
+    }
+
+    /// The number of cubes inside, or None if it overflows.
+    fn volume(&self) -> Option<i128> {
+        self.bounds.iter().try_fold(1i128, |volume, (min, max)| {
+            volume.checked_mul((*max as i128) - (*min as i128) + 1)
+        })
     }
 
-    count
+    fn intersect(&self, other: &Cuboid) -> Option<Cuboid> {
+        assert_eq!(self.bounds.len(), other.bounds.len());
+        let bounds = self
+            .bounds
+            .iter()
+            .zip(&other.bounds)
+            .map(|((min1, max1), (min2, max2))| {
+                let (min, max) = (*min1.max(min2), *max1.min(max2));
+                if min > max {
+                    return None;
+                }
+                Some((min, max))
+            })
+            .collect::<Option<_>>()?;
+        Some(Cuboid { bounds })
+    }
 }
 
-#[derive(Clone, Copy)]
-struct Cuboid {
+#[derive(Clone)]
+struct Step {
     toggle: bool,
-    x_min: i32,
-    x_max: i32,
-    y_min: i32,
-    y_max: i32,
-    z_min: i32,
-    z_max: i32,
+    cuboid: Cuboid,
 }
 
-impl Cuboid {
-    fn parse(data: &str) -> Cuboid {
-        let (toggle, coords) = data.split_once(" ").unwrap();
+impl Step {
+    /// Parse a step, returning the names of its axes alongside it.
+    fn parse(data: &str) -> Option<(Vec<&str>, Step)> {
+        let (toggle, ranges) = data.split_once(' ')?;
         let toggle = match toggle {
             "off" => false,
             "on" => true,
-            _ => unreachable!(),
+            _ => return None,
         };
 
-        let coords: Vec<&str> = coords
-            .split(",")
-            .map(|s| s.split("=").last().unwrap())
-            .collect();
-        let (xs, ys, zs) = (coords[0], coords[1], coords[2]);
-
-        let (x_min, x_max) = xs.split_once("..").unwrap();
-        let (y_min, y_max) = ys.split_once("..").unwrap();
-        let (z_min, z_max) = zs.split_once("..").unwrap();
-
-        Cuboid {
-            toggle,
-            x_min: x_min.parse().unwrap(),
-            x_max: x_max.parse().unwrap(),
-            y_min: y_min.parse().unwrap(),
-            y_max: y_max.parse().unwrap(),
-            z_min: z_min.parse().unwrap(),
-            z_max: z_max.parse().unwrap(),
+        let (names, cuboid) = Cuboid::parse(ranges)?;
+        Some((names, Step { toggle, cuboid }))
+    }
+}
+
+fn parse_point(data: &str) -> Option<Vec<i64>> {
+    data.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+fn format_volume(volume: Option<i128>) -> String {
+    match volume {
+        Some(volume) => volume.to_string(),
+        None => "too many".to_string(),
+    }
+}
+
+/// Step through the reboot sequence, answering queries from stdin in between.
+fn interact(solver: &Day22Solver) {
+    let steps = &solver.steps;
+    let mut reactor = Reactor::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap();
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match command {
+            "step" => {
+                let count = if args.is_empty() { Ok(1) } else { args.parse() };
+                match count {
+                    Ok(count) => {
+                        for step in steps.iter().skip(reactor.history.len()).take(count) {
+                            reactor.apply(step);
+                        }
+                        println!(
+                            "applied {} of {} steps, {} cubes on",
+                            reactor.history.len(),
+                            steps.len(),
+                            format_volume(reactor.volume())
+                        );
+                    }
+                    Err(_) => println!("invalid step count"),
+                }
+            }
+            "count" => match solver.region(args) {
+                Some(region) => {
+                    println!("{} cubes on", format_volume(reactor.volume_within(&region)))
+                }
+                None => println!("invalid region"),
+            },
+            "point" => match parse_point(args).filter(|point| point.len() == solver.axes.len()) {
+                // steps are numbered from 1, like the lines of the input
+                Some(point) => match reactor.last_set(&point) {
+                    Some((i, true)) => println!("on since step {}", i + 1),
+                    Some((i, false)) => println!("off since step {}", i + 1),
+                    None => println!("off, never set"),
+                },
+                None => println!("invalid point"),
+            },
+            "" => {}
+            _ => println!(
+                "commands: step [n], count {}, point {}",
+                solver
+                    .axes
+                    .iter()
+                    .map(|axis| format!("{}=a..b", axis))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                solver.axes.join(",")
+            ),
         }
     }
 }
@@ -178,6 +303,15 @@ mod day22tests {
     use super::*;
     use indoc::indoc;
 
+    static SMALL_EXAMPLE_DATA: &'static str = indoc!(
+        "
+        on x=10..12,y=10..12,z=10..12
+        on x=11..13,y=11..13,z=11..13
+        off x=9..11,y=9..11,z=9..11
+        on x=10..10,y=10..10,z=10..10
+    "
+    );
+
     static EXAMPLE_DATA1: &'static str = indoc!(
         "
         on x=-20..26,y=-36..17,z=-47..7
@@ -272,30 +406,128 @@ mod day22tests {
 
     #[test]
     fn test_parse() {
-        let cuboid = Cuboid::parse("on x=10..12,y=11..13,z=14..16");
-        assert!(cuboid.toggle);
-        assert_eq!((cuboid.x_min, cuboid.x_max), (10, 12));
-        assert_eq!((cuboid.y_min, cuboid.y_max), (11, 13));
-        assert_eq!((cuboid.z_min, cuboid.z_max), (14, 16));
-
-        let cuboid = Cuboid::parse("off x=10..12,y=11..13,z=14..16");
-        assert!(!cuboid.toggle);
-        assert_eq!((cuboid.x_min, cuboid.x_max), (10, 12));
-        assert_eq!((cuboid.y_min, cuboid.y_max), (11, 13));
-        assert_eq!((cuboid.z_min, cuboid.z_max), (14, 16));
+        let (axes, step) = Step::parse("on x=10..12,y=11..13,z=14..16").unwrap();
+        assert_eq!(axes, vec!["x", "y", "z"]);
+        assert!(step.toggle);
+        assert_eq!(step.cuboid.bounds, vec![(10, 12), (11, 13), (14, 16)]);
+
+        let (axes, step) = Step::parse("off x=10..12,y=11..13,z=14..16").unwrap();
+        assert_eq!(axes, vec!["x", "y", "z"]);
+        assert!(!step.toggle);
+        assert_eq!(step.cuboid.bounds, vec![(10, 12), (11, 13), (14, 16)]);
+
+        let (axes, step) = Step::parse("on t=0..99,room=-1..1").unwrap();
+        assert_eq!(axes, vec!["t", "room"]);
+        assert_eq!(step.cuboid.bounds, vec![(0, 99), (-1, 1)]);
+
+        assert!(Step::parse("toggle x=1..2").is_none());
+        assert!(Step::parse("on x=1..").is_none());
+
+        // ranges must run from low to high
+        assert!(Step::parse("on x=5..1,y=0..0,z=0..0").is_none());
+        assert!(Cuboid::parse("x=0..0,y=2..-2").is_none());
     }
 
     #[test]
     fn test_sanity() {
-        let solver = Day22Solver {
-            cuboids: vec![
-                Cuboid::parse("on x=1..10,y=1..10,z=1..1"),
-                Cuboid::parse("on x=10..11,y=10..11,z=1..1"),
-            ],
-        };
+        let solver = Day22Solver::new("on x=1..10,y=1..10,z=1..1\non x=10..11,y=10..11,z=1..1");
         assert_eq!(solver.solve1(), Some(103));
     }
 
+    #[test]
+    fn test_small_example() {
+        let solver = Day22Solver::new(SMALL_EXAMPLE_DATA.trim());
+
+        let mut reactor = Reactor::new();
+        let volumes: Vec<i128> = solver
+            .steps
+            .iter()
+            .map(|step| {
+                reactor.apply(step);
+                reactor.volume().unwrap()
+            })
+            .collect();
+        assert_eq!(volumes, vec![27, 46, 38, 39]);
+    }
+
+    #[test]
+    fn test_incremental() {
+        let solver = Day22Solver::new(SMALL_EXAMPLE_DATA.trim());
+        let region = solver.region("x=10..10,y=10..12,z=10..12").unwrap();
+        let mut reactor = Reactor::new();
+
+        reactor.apply(&solver.steps[0]);
+        assert_eq!(reactor.volume_within(&region), Some(9));
+        assert_eq!(reactor.last_set(&[13, 13, 13]), None);
+
+        reactor.apply(&solver.steps[1]);
+        assert_eq!(reactor.last_set(&[13, 13, 13]), Some((1, true)));
+        assert_eq!(reactor.last_set(&[10, 10, 10]), Some((0, true)));
+
+        reactor.apply(&solver.steps[2]);
+        assert_eq!(reactor.volume_within(&region), Some(5));
+        assert_eq!(reactor.last_set(&[10, 10, 10]), Some((2, false)));
+        assert_eq!(reactor.last_set(&[12, 12, 12]), Some((1, true)));
+
+        reactor.apply(&solver.steps[3]);
+        assert_eq!(reactor.volume_within(&region), Some(6));
+        assert_eq!(reactor.last_set(&[10, 10, 10]), Some((3, true)));
+        assert_eq!(reactor.last_set(&[0, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_query_region() {
+        let solver = Day22Solver::new(EXAMPLE_DATA2.trim());
+        let mut reactor = Reactor::new();
+        for step in &solver.steps {
+            reactor.apply(step);
+        }
+
+        let region = solver.region("x=-50..50,y=-50..50,z=-50..50").unwrap();
+        assert_eq!(reactor.volume_within(&region), Some(474140));
+        assert_eq!(solver.lit_within(&region), Some(474140));
+        assert_eq!(solver.solve1(), Some(474140));
+
+        // axes can be given in any order, or left out entirely
+        assert_eq!(solver.region("z=-50..50,x=-50..50,y=-50..50"), Some(region));
+        assert_eq!(
+            solver.region("y=0..0").unwrap().bounds,
+            vec![(i64::MIN, i64::MAX), (0, 0), (i64::MIN, i64::MAX)]
+        );
+        assert_eq!(solver.region("w=0..0"), None);
+    }
+
+    #[test]
+    fn test_dimensions() {
+        // rectangle union areas
+        let solver = Day22Solver::new("on x=0..9,y=0..9\non x=5..14,y=5..14\noff x=0..0,y=0..0");
+        assert_eq!(solver.solve2(), Some(174));
+        assert_eq!(
+            solver.lit_within(&solver.region("y=0..0").unwrap()),
+            Some(9)
+        );
+
+        // hypervolumes
+        let solver =
+            Day22Solver::new("on a=0..1,b=0..1,c=0..1,d=0..1\non a=1..2,b=1..2,c=1..2,d=1..2");
+        assert_eq!(solver.solve2(), Some(31));
+
+        // too many cubes to count
+        let solver =
+            Day22Solver::new("on a=0..9999999999,b=0..9999999999,c=0..9999999999,d=0..9999999999");
+        assert_eq!(solver.solve2(), None);
+        let region = solver.region("a=0..0,b=0..0,c=0..0").unwrap();
+        assert_eq!(solver.lit_within(&region), Some(10000000000));
+        let region = solver.region("a=0..0,b=0..0").unwrap();
+        assert_eq!(solver.lit_within(&region), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "every step must use the same axes")]
+    fn test_mismatched_axes() {
+        Day22Solver::new("on x=0..9,y=0..9\non x=0..9,z=0..9");
+    }
+
     #[test]
     fn test_example_part1() {
         let solver = Day22Solver::new(EXAMPLE_DATA1.trim());
@@ -310,5 +542,26 @@ mod day22tests {
 }
 
 fn main() {
-    solve_file::<Day22Solver, u64>("day22.txt");
+    if std::env::args().any(|arg| arg == "--interactive") {
+        let solver = Day22Solver::new(&load_file("day22.txt"));
+        interact(&solver);
+        return;
+    }
+
+    let region = std::env::args().find_map(|arg| arg.strip_prefix("--region=").map(String::from));
+    match region {
+        Some(region) => {
+            let solver = Day22Solver::new(&load_file("day22.txt"));
+            let region = solver.region(&region).unwrap();
+            let mut reactor = Reactor::new();
+            for step in &solver.steps {
+                reactor.apply(step);
+            }
+            println!(
+                "on within region: {}",
+                format_volume(reactor.volume_within(&region))
+            );
+        }
+        None => solve_file::<Day22Solver, u64>("day22.txt"),
+    }
 }
@@ -1,151 +1,321 @@
 #![feature(test)]
 
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
+use std::collections::HashMap;
+use std::fmt;
 
 struct Day4Solver {
     draws: Vec<u64>,
     boards: Vec<Board>,
+    rules: Rules,
 }
 
-#[derive(Clone, Debug)]
-struct Board {
-    data: [[Option<u64>; 5]; 5],
+/// Which lines count as a win. Diagonals only apply to square boards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rules {
+    rows: bool,
+    columns: bool,
+    diagonals: bool,
+    full: bool,
 }
 
-impl Board {
-    fn new(contents: [[u64; 5]; 5]) -> Self {
-        let mut data = [[None; 5]; 5];
-        for i in 0..5 {
-            for j in 0..5 {
-                data[i][j] = Some(contents[i][j]);
-            }
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            rows: true,
+            columns: true,
+            diagonals: false,
+            full: false,
         }
-        Self { data }
     }
+}
 
-    fn new_vec(contents: Vec<Vec<u64>>) -> Self {
-        let mut data = [[None; 5]; 5];
-        for i in 0..5 {
-            for j in 0..5 {
-                data[i][j] = Some(contents[i][j]);
+impl Rules {
+    /// Parse a comma separated list of rules, e.g. `rows,columns,diagonals`.
+    fn parse(data: &str) -> Option<Self> {
+        let mut rules = Rules {
+            rows: false,
+            columns: false,
+            diagonals: false,
+            full: false,
+        };
+        for rule in data.split(',') {
+            match rule.trim() {
+                "rows" => rules.rows = true,
+                "columns" => rules.columns = true,
+                "diagonals" => rules.diagonals = true,
+                "full" => rules.full = true,
+                _ => return None,
             }
         }
-        Self { data }
+        Some(rules)
     }
+}
 
-    fn unmarked(&self) -> u64 {
-        // calculate sum of unmarked numbers in board, used as part of scoring
-        let mut sum = 0;
-        for i in 0..5 {
-            for j in 0..5 {
-                if let Some(value) = self.data[i][j] {
-                    sum += value;
-                }
-            }
-        }
-        sum
-    }
-
-    fn mark(&mut self, value: u64) -> bool {
-        for i in 0..5 {
-            for j in 0..5 {
-                if self.data[i][j] == Some(value) {
-                    // mark item as used
-                    self.data[i][j] = None;
-
-                    // scan horizontally to see if row is complete
-                    let mut won = true;
-                    for di in 0..5 {
-                        if self.data[di][j] != None {
-                            won = false;
-                            break;
-                        }
-                    }
-                    if won {
-                        return true;
-                    }
-
-                    // scan vertically to see if column is complete
-                    let mut won = true;
-                    for dj in 0..5 {
-                        if self.data[i][dj] != None {
-                            won = false;
-                            break;
-                        }
-                    }
-                    return won;
-                }
-            }
+/// A completed line on a board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Line {
+    Row(usize),
+    Column(usize),
+    Diagonal,
+    AntiDiagonal,
+    Full,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Row(row) => write!(f, "row {}", row),
+            Line::Column(col) => write!(f, "column {}", col),
+            Line::Diagonal => write!(f, "diagonal"),
+            Line::AntiDiagonal => write!(f, "anti-diagonal"),
+            Line::Full => write!(f, "full card"),
         }
-        false
     }
 }
 
-impl Solver<u64> for Day4Solver {
-    fn new(problem: &str) -> Self {
-        let mut lines = problem.split("\n").filter(|line| line.len() > 0);
+#[derive(Clone, Debug)]
+struct Board {
+    width: usize,
+    height: usize,
+    data: Vec<Option<u64>>,
 
-        let draws: Vec<u64> = lines
-            .next()
-            .unwrap()
-            .split(",")
-            .map(|n| n.parse().unwrap())
+    // where each value is on the board, and how many cells of each line
+    // have been marked, so that marking doesn't need to scan the board
+    index: HashMap<u64, Vec<usize>>,
+    row_hits: Vec<usize>,
+    column_hits: Vec<usize>,
+    diagonal_hits: usize,
+    anti_diagonal_hits: usize,
+    marked: usize,
+    unmarked: u64,
+}
+
+impl Board {
+    fn new<R: AsRef<[u64]>>(rows: &[R]) -> Self {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.as_ref().len());
+        assert!(
+            rows.iter().all(|row| row.as_ref().len() == width),
+            "board rows have different lengths"
+        );
+
+        let values: Vec<u64> = rows
+            .iter()
+            .flat_map(|row| row.as_ref().iter().copied())
             .collect();
-        let board_lines: Vec<Vec<u64>> = lines
+        let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (position, value) in values.iter().enumerate() {
+            index.entry(*value).or_default().push(position);
+        }
+
+        Self {
+            width,
+            height,
+            data: values.iter().map(|value| Some(*value)).collect(),
+            index,
+            row_hits: vec![0; height],
+            column_hits: vec![0; width],
+            diagonal_hits: 0,
+            anti_diagonal_hits: 0,
+            marked: 0,
+            unmarked: values.iter().sum(),
+        }
+    }
+
+    fn parse(block: &str) -> Self {
+        let rows: Vec<Vec<u64>> = block
+            .lines()
             .map(|line| {
                 line.split_whitespace()
                     .map(|n| n.parse().unwrap())
                     .collect()
             })
             .collect();
+        Self::new(&rows)
+    }
 
-        let boards: Vec<Board> = (0..board_lines.len())
-            .step_by(5)
-            .map(|i| Board::new_vec(board_lines[i..i + 5].to_vec()))
-            .collect();
-        Self { draws, boards }
+    fn unmarked(&self) -> u64 {
+        // sum of unmarked numbers in board, used as part of scoring
+        self.unmarked
     }
 
-    fn solve1(&self) -> Option<u64> {
-        let mut boards = self.boards.clone();
-        for x in &self.draws {
-            for board in &mut boards {
-                if board.mark(*x) {
-                    // first board with a line wins
-                    return Some(x * board.unmarked());
-                }
+    /// Mark a value on the board, returning a line it completed.
+    fn mark(&mut self, value: u64, rules: &Rules) -> Option<Line> {
+        let mut won = None;
+        for position in self.index.remove(&value).unwrap_or_default() {
+            let (row, col) = (position / self.width, position % self.width);
+
+            // mark item as used
+            self.data[position] = None;
+            self.unmarked -= value;
+            self.marked += 1;
+            self.row_hits[row] += 1;
+            self.column_hits[col] += 1;
+            if row == col {
+                self.diagonal_hits += 1;
             }
+            if row + col + 1 == self.width {
+                self.anti_diagonal_hits += 1;
+            }
+
+            won = won.or_else(|| self.completed(row, col, rules));
         }
+        won
+    }
+
+    /// Find a line through a cell that has been completely marked.
+    fn completed(&self, row: usize, col: usize, rules: &Rules) -> Option<Line> {
+        let square = self.width == self.height;
 
-        None
+        if rules.rows && self.row_hits[row] == self.width {
+            Some(Line::Row(row))
+        } else if rules.columns && self.column_hits[col] == self.height {
+            Some(Line::Column(col))
+        } else if rules.diagonals && square && row == col && self.diagonal_hits == self.width {
+            Some(Line::Diagonal)
+        } else if rules.diagonals
+            && square
+            && row + col + 1 == self.width
+            && self.anti_diagonal_hits == self.width
+        {
+            Some(Line::AntiDiagonal)
+        } else if rules.full && self.marked == self.data.len() {
+            Some(Line::Full)
+        } else {
+            None
+        }
+    }
+}
+
+impl Solver<u64> for Day4Solver {
+    fn new(problem: &str) -> Self {
+        Self::with_rules(problem, Rules::default())
+    }
+
+    fn solve1(&self) -> Option<u64> {
+        // first board with a line wins
+        self.wins().next().map(|win| win.score)
     }
 
     fn solve2(&self) -> Option<u64> {
-        let mut boards: Vec<Option<Board>> =
-            self.boards.clone().into_iter().map(Option::Some).collect();
-        let mut in_play = boards.len();
-
-        for x in &self.draws {
-            for i in 0..boards.len() {
-                if let Some(board) = &mut boards[i] {
-                    if board.mark(*x) {
-                        if in_play == 1 {
-                            // last board with a line wins
-                            return Some(x * board.unmarked());
-                        } else {
-                            boards[i] = None;
-                            in_play -= 1;
-                        }
-                    }
+        // last board with a line wins
+        self.wins().last().map(|win| win.score)
+    }
+}
+
+/// A board completing a line, and the score it won with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Win {
+    board: usize,
+    draw: usize,
+    line: Line,
+    score: u64,
+}
+
+/// The boards that won on a single draw.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Event {
+    draw: usize,
+    value: u64,
+    wins: Vec<Win>,
+}
+
+/// Plays through the draws, yielding an event for each one. Boards drop out
+/// of the game once they've won.
+struct Game<'a> {
+    rules: &'a Rules,
+    draws: std::iter::Enumerate<std::slice::Iter<'a, u64>>,
+    boards: Vec<Option<Board>>,
+}
+
+impl<'a> Iterator for Game<'a> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (draw, value) = self.draws.next()?;
+        let mut wins = vec![];
+        for (i, slot) in self.boards.iter_mut().enumerate() {
+            if let Some(board) = slot {
+                if let Some(line) = board.mark(*value, self.rules) {
+                    wins.push(Win {
+                        board: i,
+                        draw,
+                        line,
+                        score: value * board.unmarked(),
+                    });
+                    *slot = None;
                 }
             }
         }
-
-        None
+        Some(Event {
+            draw,
+            value: *value,
+            wins,
+        })
     }
 }
 
-impl Day4Solver {}
+impl Day4Solver {
+    fn with_rules(problem: &str, rules: Rules) -> Self {
+        let mut blocks = problem
+            .split("\n\n")
+            .filter(|block| !block.trim().is_empty());
+
+        let draws: Vec<u64> = blocks
+            .next()
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect();
+        let boards: Vec<Board> = blocks.map(|block| Board::parse(block.trim())).collect();
+        Self {
+            draws,
+            boards,
+            rules,
+        }
+    }
+
+    fn play(&self) -> Game<'_> {
+        Game {
+            rules: &self.rules,
+            draws: self.draws.iter().enumerate(),
+            boards: self.boards.iter().cloned().map(Some).collect(),
+        }
+    }
+
+    /// Every win in the order they happen.
+    fn wins(&self) -> impl Iterator<Item = Win> + '_ {
+        self.play().flat_map(|event| event.wins)
+    }
+
+    /// How each board won, if it did at all.
+    fn timeline(&self) -> Vec<Option<Win>> {
+        let mut timeline = vec![None; self.boards.len()];
+        for win in self.wins() {
+            timeline[win.board] = Some(win);
+        }
+        timeline
+    }
+
+    fn print_timeline(&self) {
+        for event in self.play() {
+            for win in event.wins {
+                println!(
+                    "draw {} ({}): board {} wins with {}, scoring {}",
+                    event.draw, event.value, win.board, win.line, win.score
+                );
+            }
+        }
+        for (i, win) in self.timeline().iter().enumerate() {
+            match win {
+                Some(win) => println!("board {}: won on draw {}", i, win.draw),
+                None => println!("board {}: never won", i),
+            }
+        }
+    }
+}
 
 #[cfg(test)]
 mod day4tests {
@@ -153,6 +323,7 @@ mod day4tests {
 
     use super::*;
     use advent_of_code_2021::solver::{load_file, solve};
+    use indoc::indoc;
     use test::Bencher;
 
     static EXAMPLE_DRAWS: [u64; 27] = [
@@ -187,7 +358,11 @@ mod day4tests {
     fn test_example_part1() {
         let solver = Day4Solver {
             draws: EXAMPLE_DRAWS.to_vec(),
-            boards: EXAMPLE_BOARDS.map(Board::new).to_vec(),
+            boards: EXAMPLE_BOARDS
+                .iter()
+                .map(|board| Board::new(board))
+                .collect(),
+            rules: Rules::default(),
         };
         assert_eq!(solver.solve1(), Some(4512));
     }
@@ -196,11 +371,122 @@ mod day4tests {
     fn test_example_part2() {
         let solver = Day4Solver {
             draws: EXAMPLE_DRAWS.to_vec(),
-            boards: EXAMPLE_BOARDS.map(Board::new).to_vec(),
+            boards: EXAMPLE_BOARDS
+                .iter()
+                .map(|board| Board::new(board))
+                .collect(),
+            rules: Rules::default(),
         };
         assert_eq!(solver.solve2(), Some(1924));
     }
 
+    #[test]
+    fn test_timeline() {
+        let solver = Day4Solver {
+            draws: EXAMPLE_DRAWS.to_vec(),
+            boards: EXAMPLE_BOARDS
+                .iter()
+                .map(|board| Board::new(board))
+                .collect(),
+            rules: Rules::default(),
+        };
+        let timeline = solver.timeline();
+        assert_eq!(
+            timeline[2],
+            Some(Win {
+                board: 2,
+                draw: 11,
+                line: Line::Row(0),
+                score: 4512
+            })
+        );
+        assert_eq!(
+            timeline[1].map(|win| (win.draw, win.score)),
+            Some((14, 1924))
+        );
+        assert!(timeline[0].unwrap().draw < 14);
+
+        // the order boards win in
+        let order: Vec<usize> = solver.wins().map(|win| win.board).collect();
+        assert_eq!(order, vec![2, 0, 1]);
+        assert_eq!(solver.wins().nth(1).map(|win| win.board), Some(0));
+
+        // an event for every draw, even once every board has won
+        let events: Vec<Event> = solver.play().collect();
+        assert_eq!(events.len(), EXAMPLE_DRAWS.len());
+        assert_eq!(events[11].value, 24);
+        assert_eq!(events[11].wins.len(), 1);
+        assert!(events[15..].iter().all(|event| event.wins.is_empty()));
+    }
+
+    #[test]
+    fn test_parse() {
+        let solver = Day4Solver::new(indoc! {"
+            1,2,3
+
+             1  2  3
+             4  5  6
+
+            7 8
+            9 1
+            2 3
+        "});
+        assert_eq!(solver.draws, vec![1, 2, 3]);
+        assert_eq!(solver.boards.len(), 2);
+        assert_eq!((solver.boards[0].width, solver.boards[0].height), (3, 2));
+        assert_eq!((solver.boards[1].width, solver.boards[1].height), (2, 3));
+        assert_eq!(solver.boards[1].data[4], Some(2));
+    }
+
+    #[test]
+    fn test_mark() {
+        let mut board = Board::new(&[[1, 2, 3], [4, 5, 6]]);
+        let rules = Rules::default();
+        assert_eq!(board.mark(2, &rules), None);
+        assert_eq!(board.mark(5, &rules), Some(Line::Column(1)));
+        assert_eq!(board.mark(4, &rules), None);
+        assert_eq!(board.mark(6, &rules), Some(Line::Row(1)));
+        assert_eq!(board.unmarked(), 4);
+    }
+
+    #[test]
+    fn test_mark_repeated() {
+        let rules = Rules::default();
+        let mut board = Board::new(&[[1, 2], [2, 3]]);
+        assert_eq!(board.mark(4, &rules), None);
+        assert_eq!(board.mark(2, &rules), None);
+        assert_eq!(board.data[1], None);
+        assert_eq!(board.data[2], None);
+        assert_eq!(board.unmarked(), 4);
+
+        // marking the same value twice doesn't count twice
+        assert_eq!(board.mark(2, &rules), None);
+        assert_eq!(board.mark(3, &rules), Some(Line::Row(1)));
+        assert_eq!(board.unmarked(), 1);
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules = Rules::parse("diagonals").unwrap();
+        let mut board = Board::new(&[[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        assert_eq!(board.mark(1, &rules), None);
+        assert_eq!(board.mark(2, &rules), None);
+        assert_eq!(board.mark(3, &rules), None);
+        assert_eq!(board.mark(5, &rules), None);
+        assert_eq!(board.mark(7, &rules), Some(Line::AntiDiagonal));
+        assert_eq!(board.mark(9, &rules), Some(Line::Diagonal));
+
+        let rules = Rules::parse("full").unwrap();
+        let mut board = Board::new(&[[1, 2], [3, 4]]);
+        assert_eq!(board.mark(1, &rules), None);
+        assert_eq!(board.mark(2, &rules), None);
+        assert_eq!(board.mark(3, &rules), None);
+        assert_eq!(board.mark(4, &rules), Some(Line::Full));
+
+        assert_eq!(Rules::parse("rows,columns"), Some(Rules::default()));
+        assert_eq!(Rules::parse("corners"), None);
+    }
+
     #[bench]
     fn bench_solve_file(b: &mut Bencher) {
         let problem = load_file("day4.txt");
@@ -209,5 +495,16 @@ mod day4tests {
 }
 
 fn main() {
-    solve_file::<Day4Solver, u64>("day4.txt");
+    let rules = std::env::args().find_map(|arg| arg.strip_prefix("--rules=").map(String::from));
+    let rules = rules.map_or(Rules::default(), |rules| Rules::parse(&rules).unwrap());
+    if std::env::args().any(|arg| arg == "--timeline") {
+        let solver = Day4Solver::with_rules(&load_file("day4.txt"), rules);
+        solver.print_timeline();
+    } else if rules != Rules::default() {
+        let solver = Day4Solver::with_rules(&load_file("day4.txt"), rules);
+        println!("part 1: {}", solver.solve1().unwrap());
+        println!("part 2: {}", solver.solve2().unwrap());
+    } else {
+        solve_file::<Day4Solver, u64>("day4.txt");
+    }
 }
@@ -1,116 +1,318 @@
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
 struct Day5Solver {
     vents: Vec<Line>,
-    max_size: usize,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 struct Line {
-    source: (usize, usize),
-    dest: (usize, usize),
+    source: (i64, i64),
+    dest: (i64, i64),
 }
 
-impl Solver<u64> for Day5Solver {
-    fn new(problem: &str) -> Self {
-        let vents: Vec<Line> = problem
-            .split("\n")
-            .map(|line| {
-                let (source, dest) = line.split_once(" -> ").unwrap();
-                let (sx, sy) = source.split_once(",").unwrap();
-                let (sx, sy) = (sx.parse().unwrap(), sy.parse().unwrap());
-                let (dx, dy) = dest.split_once(",").unwrap();
-                let (dx, dy) = (dx.parse().unwrap(), dy.parse().unwrap());
-
-                Line {
-                    source: (sx, sy),
-                    dest: (dx, dy),
-                }
+#[derive(Debug, PartialEq, Eq)]
+enum ParseLineError {
+    MissingArrow,
+    InvalidPoint(String),
+}
+
+impl fmt::Display for ParseLineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseLineError::MissingArrow => write!(f, "expected two points separated by \" -> \""),
+            ParseLineError::InvalidPoint(point) => write!(f, "invalid point \"{}\"", point),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Sloped,
+}
+
+impl Direction {
+    /// Parse a comma separated list of directions, e.g. `horizontal,diagonal`.
+    fn parse_list(data: &str) -> Option<Vec<Direction>> {
+        data.split(',')
+            .map(|direction| match direction.trim() {
+                "horizontal" => Some(Direction::Horizontal),
+                "vertical" => Some(Direction::Vertical),
+                "diagonal" => Some(Direction::Diagonal),
+                "sloped" => Some(Direction::Sloped),
+                _ => None,
             })
-            .collect();
-        let max_size: usize = 1 + vents.iter().fold(0, |acc, vent| {
-            let idxs = [acc, vent.source.0, vent.source.1, vent.dest.0, vent.dest.1];
-            idxs.into_iter().reduce(usize::max).unwrap()
-        });
-        Self { vents, max_size }
+            .collect()
     }
+}
 
-    fn solve1(&self) -> Option<u64> {
-        let mut grid = Grid::new(self.max_size);
-        for vent in &self.vents {
-            grid.fill(vent.source, vent.dest, false);
+impl Line {
+    fn parse(data: &str) -> Result<Line, ParseLineError> {
+        let parse_point = |point: &str| {
+            let invalid = || ParseLineError::InvalidPoint(point.to_string());
+            let (x, y) = point.split_once(',').ok_or_else(invalid)?;
+            let x = x.trim().parse().map_err(|_| invalid())?;
+            let y = y.trim().parse().map_err(|_| invalid())?;
+            Ok((x, y))
+        };
+
+        let (source, dest) = data
+            .split_once(" -> ")
+            .ok_or(ParseLineError::MissingArrow)?;
+        Ok(Line {
+            source: parse_point(source)?,
+            dest: parse_point(dest)?,
+        })
+    }
+
+    fn direction(&self) -> Direction {
+        let (xdiff, ydiff) = (
+            self.source.0.abs_diff(self.dest.0),
+            self.source.1.abs_diff(self.dest.1),
+        );
+        if ydiff == 0 {
+            Direction::Horizontal
+        } else if xdiff == 0 {
+            Direction::Vertical
+        } else if xdiff == ydiff {
+            Direction::Diagonal
+        } else {
+            Direction::Sloped
+        }
+    }
+
+    /// Rasterise the line with Bresenham's algorithm, which gives the exact
+    /// points for horizontal, vertical and 45° lines.
+    fn points(&self) -> Points {
+        let (dx, dy) = (self.dest.0 - self.source.0, self.dest.1 - self.source.1);
+        Points {
+            current: Some(self.source),
+            dest: self.dest,
+            step: (dx.signum(), dy.signum()),
+            delta: (dx.abs(), -dy.abs()),
+            error: dx.abs() - dy.abs(),
         }
+    }
+}
 
+struct Points {
+    current: Option<(i64, i64)>,
+    dest: (i64, i64),
+    step: (i64, i64),
+    delta: (i64, i64),
+    error: i64,
+}
+
+impl Iterator for Points {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, y) = self.current?;
+        if (x, y) == self.dest {
+            self.current = None;
+            return Some((x, y));
+        }
+
+        let mut next = (x, y);
+        let e2 = 2 * self.error;
+        if e2 >= self.delta.1 {
+            self.error += self.delta.1;
+            next.0 += self.step.0;
+        }
+        if e2 <= self.delta.0 {
+            self.error += self.delta.0;
+            next.1 += self.step.1;
+        }
+        self.current = Some(next);
+        Some((x, y))
+    }
+}
+
+impl Solver<u64> for Day5Solver {
+    fn new(problem: &str) -> Self {
+        match Self::parse(problem) {
+            Ok(solver) => solver,
+            Err((line, err)) => panic!("line {}: {}", line, err),
+        }
+    }
+
+    fn solve1(&self) -> Option<u64> {
+        let grid = self.grid(&[Direction::Horizontal, Direction::Vertical]);
         Some(grid.count() as u64)
     }
 
     fn solve2(&self) -> Option<u64> {
-        let mut grid = Grid::new(self.max_size);
+        let grid = self.grid(&[
+            Direction::Horizontal,
+            Direction::Vertical,
+            Direction::Diagonal,
+        ]);
+        Some(grid.count() as u64)
+    }
+}
+
+impl Day5Solver {
+    /// Parse the vents, reporting the (1-based) line number of any error.
+    fn parse(problem: &str) -> Result<Self, (usize, ParseLineError)> {
+        let vents = problem
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| Line::parse(line).map_err(|err| (i + 1, err)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { vents })
+    }
+
+    /// Fill a grid with the vents running in any of the given directions.
+    fn grid(&self, directions: &[Direction]) -> Grid {
+        let mut grid = Grid::new();
         for vent in &self.vents {
-            grid.fill(vent.source, vent.dest, true);
+            if directions.contains(&vent.direction()) {
+                grid.fill(vent);
+            }
         }
-        Some(grid.count() as u64)
+        grid
     }
 }
 
+/// The number of vents covering each point, only storing the points that
+/// have any.
 struct Grid {
-    data: Vec<Vec<u64>>,
+    data: HashMap<(i64, i64), u64>,
 }
 
 impl Grid {
-    fn new(size: usize) -> Grid {
+    fn new() -> Grid {
         Grid {
-            data: vec![vec![0; size]; size],
+            data: HashMap::new(),
         }
     }
 
-    fn fill(&mut self, (sx, sy): (usize, usize), (dx, dy): (usize, usize), allow_diagonal: bool) {
-        let xdiff = usize::max(sx, dx) - usize::min(sx, dx);
-        let ydiff = usize::max(sy, dy) - usize::min(sy, dy);
-        if xdiff == 0 {
-            // vertical
-            for y in usize::min(sy, dy)..usize::max(sy, dy) + 1 {
-                self.data[sx][y] += 1;
-            }
-        } else if ydiff == 0 {
-            // horizontal
-            for x in usize::min(sx, dx)..usize::max(sx, dx) + 1 {
-                self.data[x][sy] += 1;
-            }
-        } else if xdiff == ydiff {
-            // diagonal
-            if allow_diagonal {
-                let (offset_x, offset_y): (isize, isize) = if sx < dx {
-                    if sy < dy {
-                        (1, 1)
-                    } else {
-                        (1, -1)
-                    }
-                } else {
-                    if sy < dy {
-                        (-1, 1)
-                    } else {
-                        (-1, -1)
-                    }
-                };
-
-                for i in 0..xdiff + 1 {
-                    let (offset_x, offset_y) = (offset_x * i as isize, offset_y * i as isize);
-                    let x = sx as isize + offset_x;
-                    let y = sy as isize + offset_y;
-                    self.data[x as usize][y as usize] += 1;
-                }
-            }
-        } else {
-            panic!("cannot connect points!");
+    fn fill(&mut self, line: &Line) {
+        for point in line.points() {
+            *self.data.entry(point).or_insert(0) += 1;
         }
     }
 
     fn count(&self) -> usize {
+        self.count_at_least(2)
+    }
+
+    /// The number of points covered by at least some number of vents. Points
+    /// without any vents are never counted.
+    fn count_at_least(&self, threshold: u64) -> usize {
         self.data
+            .values()
+            .filter(|overlap| **overlap >= threshold)
+            .count()
+    }
+
+    /// How many points are covered by each number of vents.
+    fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
+        for overlap in self.data.values() {
+            *histogram.entry(*overlap).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// The highest number of overlapping vents, and the points, in order,
+    /// where they overlap.
+    fn max_points(&self) -> Option<(u64, Vec<(i64, i64)>)> {
+        let max = *self.data.values().max()?;
+        let mut points: Vec<(i64, i64)> = self
+            .data
             .iter()
-            .map(|line| line.iter().filter(|overlap| **overlap >= 2).count())
-            .sum()
+            .filter(|(_, overlap)| **overlap == max)
+            .map(|(point, _)| *point)
+            .collect();
+        points.sort_unstable();
+        Some((max, points))
+    }
+
+    fn get(&self, point: (i64, i64)) -> u64 {
+        self.data.get(&point).copied().unwrap_or(0)
+    }
+
+    /// The smallest region containing every covered point.
+    fn bounds(&self) -> Option<Region> {
+        let xs = self.data.keys().map(|(x, _)| *x);
+        let ys = self.data.keys().map(|(_, y)| *y);
+        Some(Region {
+            min: (xs.clone().min()?, ys.clone().min()?),
+            max: (xs.max()?, ys.max()?),
+        })
+    }
+
+    /// Draw the grid like the puzzle does, with counts above 9 drawn as `#`.
+    fn write_ascii<W: Write>(&self, w: &mut W, region: &Region) -> io::Result<()> {
+        for y in region.min.1..=region.max.1 {
+            let row: String = (region.min.0..=region.max.0)
+                .map(|x| match self.get((x, y)) {
+                    0 => '.',
+                    n => char::from_digit(n as u32, 10).unwrap_or('#'),
+                })
+                .collect();
+            writeln!(w, "{}", row)?;
+        }
+        Ok(())
+    }
+
+    /// Write the grid as a binary PPM, shading counts from black through
+    /// red and yellow up to white at the highest count in the region.
+    fn write_ppm<W: Write>(&self, w: &mut W, region: &Region) -> io::Result<()> {
+        let (width, height) = region.size();
+        writeln!(w, "P6\n{} {}\n255", width, height)?;
+
+        let points = || {
+            (region.min.1..=region.max.1)
+                .flat_map(|y| (region.min.0..=region.max.0).map(move |x| (x, y)))
+        };
+        let max = points()
+            .map(|point| self.get(point))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for point in points() {
+            let heat = 3.0 * self.get(point) as f64 / max as f64;
+            let channel = |offset: f64| ((heat - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+            w.write_all(&[channel(0.0), channel(1.0), channel(2.0)])?;
+        }
+        Ok(())
+    }
+}
+
+/// An inclusive rectangle of points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Region {
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl Region {
+    /// Parse a region from its corners, as `x0,y0,x1,y1`.
+    fn parse(data: &str) -> Option<Region> {
+        let coords: Vec<i64> = data
+            .split(',')
+            .map(|n| n.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        match coords[..] {
+            [x0, y0, x1, y1] => Some(Region {
+                min: (x0.min(x1), y0.min(y1)),
+                max: (x0.max(x1), y0.max(y1)),
+            }),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> (i64, i64) {
+        (self.max.0 - self.min.0 + 1, self.max.1 - self.min.1 + 1)
     }
 }
 
@@ -178,14 +380,28 @@ mod day5tests {
     fn test_parse() {
         let solver = Day5Solver::new(EXAMPLE_PARSE.trim());
         assert_eq!(solver.vents, EXAMPLE_VENTS.to_vec());
-        assert_eq!(solver.max_size, 10);
+
+        assert_eq!(
+            Line::parse("-3,4 -> 1000000000,-7"),
+            Ok(Line {
+                source: (-3, 4),
+                dest: (1000000000, -7)
+            })
+        );
+        assert_eq!(
+            Line::parse("0,9 0,8").unwrap_err(),
+            ParseLineError::MissingArrow
+        );
+        assert_eq!(
+            Day5Solver::parse("0,9 -> 5,9\n8,0 -> 0,x").err(),
+            Some((2, ParseLineError::InvalidPoint("0,x".to_string())))
+        );
     }
 
     #[test]
     fn test_example_part1() {
         let solver = Day5Solver {
             vents: EXAMPLE_VENTS.to_vec(),
-            max_size: 10,
         };
         assert_eq!(solver.solve1(), Some(5));
     }
@@ -194,12 +410,165 @@ mod day5tests {
     fn test_example_part2() {
         let solver = Day5Solver {
             vents: EXAMPLE_VENTS.to_vec(),
-            max_size: 10,
         };
         assert_eq!(solver.solve2(), Some(12));
     }
+
+    #[test]
+    fn test_points() {
+        let points = |data| Line::parse(data).unwrap().points().collect::<Vec<_>>();
+        assert_eq!(points("1,1 -> 1,3"), vec![(1, 1), (1, 2), (1, 3)]);
+        assert_eq!(points("9,7 -> 7,9"), vec![(9, 7), (8, 8), (7, 9)]);
+        assert_eq!(points("2,2 -> 2,2"), vec![(2, 2)]);
+        assert_eq!(
+            points("0,0 -> 4,2"),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            Line::parse("0,0 -> 4,2").unwrap().direction(),
+            Direction::Sloped
+        );
+    }
+
+    #[test]
+    fn test_sparse() {
+        let solver = Day5Solver::new(indoc! {"
+            1000000000,-1000 -> 1000000000,1000
+            999999000,3 -> 1000001000,3
+            999999000,3 -> 999999010,3
+            0,0 -> 4,2
+        "});
+        assert_eq!(solver.solve1(), Some(12));
+        let grid = solver.grid(&[Direction::Sloped]);
+        assert_eq!(grid.data.len(), 5);
+    }
+
+    #[test]
+    fn test_render() {
+        let solver = Day5Solver::new(EXAMPLE_PARSE.trim());
+        let grid = solver.grid(&[
+            Direction::Horizontal,
+            Direction::Vertical,
+            Direction::Diagonal,
+        ]);
+        let region = grid.bounds().unwrap();
+        assert_eq!(
+            region,
+            Region {
+                min: (0, 0),
+                max: (9, 9)
+            }
+        );
+
+        let mut output = vec![];
+        grid.write_ascii(&mut output, &region).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            indoc! {"
+                1.1....11.
+                .111...2..
+                ..2.1.111.
+                ...1.2.2..
+                .112313211
+                ...1.2....
+                ..1...1...
+                .1.....1..
+                1.......1.
+                222111....
+            "}
+        );
+
+        let mut output = vec![];
+        let region = Region::parse("3,3,5,4").unwrap();
+        grid.write_ascii(&mut output, &region).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1.2\n231\n");
+
+        let mut output = vec![];
+        grid.write_ppm(&mut output, &region).unwrap();
+        let (header, pixels) = output.split_at(11);
+        assert_eq!(header, b"P6\n3 2\n255\n");
+        assert_eq!(&pixels[..9], &[255, 0, 0, 0, 0, 0, 255, 255, 0]);
+        assert_eq!(&pixels[12..15], &[255, 255, 255]);
+        assert_eq!(pixels.len(), 18);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let solver = Day5Solver::new(EXAMPLE_PARSE.trim());
+        let grid = solver.grid(&Direction::parse_list("horizontal,vertical,diagonal").unwrap());
+        assert_eq!(
+            grid.histogram().into_iter().collect::<Vec<_>>(),
+            vec![(1, 27), (2, 10), (3, 2)]
+        );
+        assert_eq!(grid.count_at_least(1), 39);
+        assert_eq!(grid.count_at_least(2), 12);
+        assert_eq!(grid.count_at_least(3), 2);
+        assert_eq!(grid.count_at_least(4), 0);
+        assert_eq!(grid.max_points(), Some((3, vec![(4, 4), (6, 4)])));
+
+        // diagonals on their own
+        let grid = solver.grid(&Direction::parse_list("diagonal").unwrap());
+        assert_eq!(grid.count(), 4);
+        assert_eq!(
+            grid.max_points(),
+            Some((2, vec![(4, 4), (5, 3), (5, 5), (6, 4)]))
+        );
+
+        assert_eq!(Direction::parse_list("horizontal,upwards"), None);
+        assert_eq!(solver.grid(&[]).max_points(), None);
+    }
 }
 
 fn main() {
-    solve_file::<Day5Solver, u64>("day5.txt");
+    let arg = |name: &str| {
+        let prefix = format!("--{}=", name);
+        std::env::args().find_map(|arg| arg.strip_prefix(&prefix).map(String::from))
+    };
+    let ascii = std::env::args().any(|arg| arg == "--ascii");
+    let stats = std::env::args().any(|arg| arg == "--stats");
+    let heatmap = arg("heatmap");
+    let directions = match arg("directions") {
+        Some(directions) => Direction::parse_list(&directions).unwrap(),
+        None => vec![
+            Direction::Horizontal,
+            Direction::Vertical,
+            Direction::Diagonal,
+        ],
+    };
+
+    if ascii || stats || heatmap.is_some() {
+        let solver = Day5Solver::new(&load_file("day5.txt"));
+        let grid = solver.grid(&directions);
+
+        if ascii || heatmap.is_some() {
+            let region = match arg("crop") {
+                Some(crop) => Region::parse(&crop).unwrap(),
+                None => grid.bounds().unwrap(),
+            };
+            if ascii {
+                grid.write_ascii(&mut io::stdout().lock(), &region).unwrap();
+            }
+            if let Some(path) = heatmap {
+                let mut file = BufWriter::new(File::create(path).unwrap());
+                grid.write_ppm(&mut file, &region).unwrap();
+            }
+        }
+
+        if stats {
+            let threshold = arg("threshold").map_or(2, |n| n.parse().unwrap());
+            println!(
+                "at least {} vents: {} points",
+                threshold,
+                grid.count_at_least(threshold)
+            );
+            for (overlap, count) in grid.histogram() {
+                println!("{} vents: {} points", overlap, count);
+            }
+            if let Some((max, points)) = grid.max_points() {
+                println!("most vents: {} at {:?}", max, points);
+            }
+        }
+    } else {
+        solve_file::<Day5Solver, u64>("day5.txt");
+    }
 }
@@ -1,51 +1,250 @@
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
+use num_bigint::BigUint;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::iter;
+
+/// How long a fish waits before spawning again, and how long a newly spawned
+/// fish waits before its first spawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Timers {
+    reset: usize,
+    spawn: usize,
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self { reset: 6, spawn: 8 }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TimerError {
+    ResetAfterSpawn(Timers),
+    TimerTooLong(usize, Timers),
+}
+
+impl fmt::Display for TimerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimerError::ResetAfterSpawn(timers) => write!(
+                f,
+                "reset timer {} is longer than spawn timer {}",
+                timers.reset, timers.spawn
+            ),
+            TimerError::TimerTooLong(timer, timers) => write!(
+                f,
+                "fish with timer {} is longer than spawn timer {}",
+                timer, timers.spawn
+            ),
+        }
+    }
+}
 
 struct Day6Solver {
-    bins: [u64; 9],
+    bins: Vec<u64>,
+    timers: Timers,
 }
 
 impl Solver<u64> for Day6Solver {
     fn new(problem: &str) -> Self {
-        let counts: Vec<usize> = problem
-            .split(",")
-            .map(|count| count.parse().unwrap())
-            .collect();
-
-        let mut bins = [0; 9];
-        for count in counts {
-            bins[count] += 1;
-        }
-        Self { bins }
+        Self::with_timers(problem, Timers::default()).unwrap()
     }
 
     fn solve1(&self) -> Option<u64> {
-        Some(self.solve(80))
+        self.population(80)
     }
 
     fn solve2(&self) -> Option<u64> {
-        Some(self.solve(256))
+        self.population(256)
     }
 }
 
 impl Day6Solver {
-    fn solve(&self, generations: u64) -> u64 {
-        let mut bins = self.bins;
-        for _ in 0..generations {
-            let tmp = bins[0];
-            for i in 0..8 {
-                bins[i] = bins[i + 1];
+    fn with_timers(problem: &str, timers: Timers) -> Result<Self, TimerError> {
+        if timers.reset > timers.spawn {
+            return Err(TimerError::ResetAfterSpawn(timers));
+        }
+
+        let mut bins = vec![0; timers.spawn + 1];
+        for count in problem.split(',') {
+            let count: usize = count.trim().parse().unwrap();
+            if count > timers.spawn {
+                return Err(TimerError::TimerTooLong(count, timers));
             }
-            bins[6] += tmp;
-            bins[8] = tmp;
+            bins[count] += 1;
         }
+        Ok(Self { bins, timers })
+    }
 
+    /// The number of fish after some generations, or None if it overflows.
+    fn population(&self, generations: u64) -> Option<u64> {
+        let bins = self.transition().pow(generations)?.apply(&self.bins)?;
+        bins.iter().try_fold(0u64, |total, n| total.checked_add(*n))
+    }
+
+    fn population_big(&self, generations: u64) -> BigUint {
+        let bins: Vec<BigUint> = self.bins.iter().map(|n| BigUint::from(*n)).collect();
+        let bins = self
+            .transition()
+            .pow(generations)
+            .unwrap()
+            .apply(&bins)
+            .unwrap();
         bins.iter().sum()
     }
+
+    /// The bins for every generation, starting with the initial state. The
+    /// counts are kept with arbitrary precision, so the series never ends.
+    fn generations(&self) -> impl Iterator<Item = Vec<BigUint>> {
+        let transition = self.transition();
+        let bins: Vec<BigUint> = self.bins.iter().map(|n| BigUint::from(*n)).collect();
+        iter::successors(Some(bins), move |bins| transition.apply(bins))
+    }
+
+    fn write_series<W: Write>(&self, w: &mut W, generations: usize) -> io::Result<()> {
+        let timers: Vec<String> = (0..self.bins.len()).map(|i| i.to_string()).collect();
+        writeln!(w, "generation,{},total", timers.join(","))?;
+        for (generation, bins) in self.generations().take(generations + 1).enumerate() {
+            let counts: Vec<String> = bins.iter().map(|n| n.to_string()).collect();
+            let total: BigUint = bins.iter().sum();
+            writeln!(w, "{},{},{}", generation, counts.join(","), total)?;
+        }
+        Ok(())
+    }
+
+    /// The matrix taking the bins for one generation to the next.
+    fn transition<T: Count>(&self) -> Matrix<T> {
+        let Timers { reset, spawn } = self.timers;
+        let mut matrix = Matrix::zero(spawn + 1);
+        for i in 0..spawn {
+            matrix.set(i, i + 1, T::one());
+        }
+        matrix.set(reset, 0, T::one());
+        // the parent and child can share a timer, so add rather than overwrite
+        let spawned = T::one().add(matrix.get(spawn, 0)).unwrap();
+        matrix.set(spawn, 0, spawned);
+        matrix
+    }
+}
+
+/// A number that can be counted with, failing if it overflows.
+trait Count: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn add(&self, other: &Self) -> Option<Self>;
+    fn mul(&self, other: &Self) -> Option<Self>;
+}
+
+impl Count for u64 {
+    fn zero() -> Self {
+        0
+    }
+    fn one() -> Self {
+        1
+    }
+    fn add(&self, other: &Self) -> Option<Self> {
+        self.checked_add(*other)
+    }
+    fn mul(&self, other: &Self) -> Option<Self> {
+        self.checked_mul(*other)
+    }
+}
+
+impl Count for BigUint {
+    fn zero() -> Self {
+        BigUint::from(0u8)
+    }
+    fn one() -> Self {
+        BigUint::from(1u8)
+    }
+    fn add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+    fn mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+#[derive(Clone)]
+struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Count> Matrix<T> {
+    fn zero(size: usize) -> Self {
+        Self {
+            size,
+            cells: vec![T::zero(); size * size],
+        }
+    }
+
+    fn identity(size: usize) -> Self {
+        let mut matrix = Self::zero(size);
+        for i in 0..size {
+            matrix.set(i, i, T::one());
+        }
+        matrix
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.size + col]
+    }
+
+    fn set(&mut self, row: usize, col: usize, value: T) {
+        self.cells[row * self.size + col] = value;
+    }
+
+    fn mul(&self, other: &Matrix<T>) -> Option<Matrix<T>> {
+        let mut result = Self::zero(self.size);
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let mut total = T::zero();
+                for k in 0..self.size {
+                    total = total.add(&self.get(row, k).mul(other.get(k, col))?)?;
+                }
+                result.set(row, col, total);
+            }
+        }
+        Some(result)
+    }
+
+    /// Raise the matrix to a power by repeated squaring.
+    fn pow(&self, mut exp: u64) -> Option<Matrix<T>> {
+        let mut result = Self::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Some(result)
+    }
+
+    fn apply(&self, vector: &[T]) -> Option<Vec<T>> {
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(T::zero(), |total, (col, n)| {
+                        total.add(&self.get(row, col).mul(n)?)
+                    })
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod day6tests {
     use super::*;
+    use indoc::indoc;
     static EXAMPLE_INPUT: &'static str = "3,4,3,1,2";
     static EXAMPLE_BINS: [u64; 9] = [0, 1, 1, 2, 1, 0, 0, 0, 0];
 
@@ -57,17 +256,146 @@ mod day6tests {
 
     #[test]
     fn test_example_part1() {
-        let solver = Day6Solver { bins: EXAMPLE_BINS };
+        let solver = Day6Solver::new(EXAMPLE_INPUT);
+        assert_eq!(solver.population(18), Some(26));
         assert_eq!(solver.solve1(), Some(5934));
     }
 
     #[test]
     fn test_example_part2() {
-        let solver = Day6Solver { bins: EXAMPLE_BINS };
+        let solver = Day6Solver::new(EXAMPLE_INPUT);
         assert_eq!(solver.solve2(), Some(26984457539));
     }
+
+    #[test]
+    fn test_overflow() {
+        let solver = Day6Solver::new(EXAMPLE_INPUT);
+        assert_eq!(solver.population_big(256), BigUint::from(26984457539u64));
+        assert_eq!(solver.population(600), None);
+        assert!(solver.population_big(600) > BigUint::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_generations() {
+        let big = |bins: &[u64]| bins.iter().map(|n| BigUint::from(*n)).collect::<Vec<_>>();
+        let solver = Day6Solver::new(EXAMPLE_INPUT);
+        let mut generations = solver.generations();
+        assert_eq!(generations.next(), Some(big(&EXAMPLE_BINS)));
+        assert_eq!(generations.next(), Some(big(&[1, 1, 2, 1, 0, 0, 0, 0, 0])));
+        assert_eq!(generations.next(), Some(big(&[1, 2, 1, 0, 0, 0, 1, 0, 1])));
+
+        let totals: Vec<BigUint> = solver
+            .generations()
+            .map(|bins| bins.iter().sum())
+            .take(81)
+            .collect();
+        assert_eq!(totals[18], BigUint::from(26u8));
+        assert_eq!(totals[80], BigUint::from(5934u16));
+    }
+
+    #[test]
+    fn test_series() {
+        let solver = Day6Solver::new(EXAMPLE_INPUT);
+        let mut output = vec![];
+        solver.write_series(&mut output, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            indoc! {"
+                generation,0,1,2,3,4,5,6,7,8,total
+                0,0,1,1,2,1,0,0,0,0,5
+                1,1,1,2,1,0,0,0,0,0,5
+                2,1,2,1,0,0,0,1,0,1,6
+            "}
+        );
+
+        // the series keeps going past where the totals overflow a u64
+        let mut output = vec![];
+        solver.write_series(&mut output, 700).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 702);
+        let total = output.lines().last().unwrap().rsplit(',').next().unwrap();
+        assert_eq!(total, solver.population_big(700).to_string());
+    }
+
+    #[test]
+    fn test_timer_errors() {
+        let timers = Timers { reset: 4, spawn: 6 };
+        assert_eq!(
+            Day6Solver::with_timers("3,4,3,1,2,7", timers).err(),
+            Some(TimerError::TimerTooLong(7, timers))
+        );
+        assert!(Day6Solver::with_timers("3,4,3,1,2,6", timers).is_ok());
+
+        let timers = Timers { reset: 7, spawn: 6 };
+        assert_eq!(
+            Day6Solver::with_timers(EXAMPLE_INPUT, timers).err(),
+            Some(TimerError::ResetAfterSpawn(timers))
+        );
+    }
+
+    #[test]
+    fn test_timers() {
+        for timers in [Timers { reset: 2, spawn: 4 }, Timers { reset: 4, spawn: 4 }] {
+            let solver = Day6Solver::with_timers(EXAMPLE_INPUT, timers).unwrap();
+
+            // step the fish one generation at a time to check against
+            let mut fish: Vec<usize> = vec![3, 4, 3, 1, 2];
+            for generation in 0..30 {
+                assert_eq!(solver.population(generation), Some(fish.len() as u64));
+                let spawned = fish.iter().filter(|timer| **timer == 0).count();
+                for timer in fish.iter_mut() {
+                    *timer = if *timer == 0 {
+                        timers.reset
+                    } else {
+                        *timer - 1
+                    };
+                }
+                fish.extend(std::iter::repeat_n(timers.spawn, spawned));
+            }
+        }
+
+        // a parent and its child restarting on the same timer
+        let timers = Timers { reset: 3, spawn: 3 };
+        let solver = Day6Solver::with_timers("0", timers).unwrap();
+        assert_eq!(solver.population(1), Some(2));
+    }
 }
 
 fn main() {
-    solve_file::<Day6Solver, u64>("day6.txt");
+    let arg = |name: &str| -> Option<usize> {
+        let prefix = format!("--{}=", name);
+        std::env::args().find_map(|arg| arg.strip_prefix(&prefix).map(|n| n.parse().unwrap()))
+    };
+    let defaults = Timers::default();
+    let timers = Timers {
+        reset: arg("reset").unwrap_or(defaults.reset),
+        spawn: arg("spawn").unwrap_or(defaults.spawn),
+    };
+
+    let load = || match Day6Solver::with_timers(&load_file("day6.txt"), timers) {
+        Ok(solver) => Some(solver),
+        Err(err) => {
+            println!("invalid timers: {}", err);
+            None
+        }
+    };
+
+    if let Some(path) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--series=").map(String::from))
+    {
+        let Some(solver) = load() else { return };
+        let mut file = BufWriter::new(File::create(path).unwrap());
+        solver
+            .write_series(&mut file, arg("days").unwrap_or(256))
+            .unwrap();
+    } else if let Some(days) = arg("days") {
+        let Some(solver) = load() else { return };
+        println!(
+            "after {} days: {}",
+            days,
+            solver.population_big(days as u64)
+        );
+    } else {
+        solve_file::<Day6Solver, u64>("day6.txt");
+    }
 }
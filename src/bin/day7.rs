@@ -1,4 +1,6 @@
-use advent_of_code_2021::solver::{solve_file, Solver};
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
 struct Day7Solver {
     positions: Vec<u64>,
@@ -22,30 +24,77 @@ impl Solver<u64> for Day7Solver {
     }
 
     fn solve1(&self) -> Option<u64> {
-        let result = self.solve(|cost| cost);
-        Some(result)
+        // the sum of distances is minimised at the median
+        let mut sorted = self.positions.clone();
+        sorted.sort_unstable();
+        let median = sorted[sorted.len() / 2];
+        Some(self.fuel(median, linear))
     }
 
     fn solve2(&self) -> Option<u64> {
-        let result = self.solve(|cost| cost * (cost + 1) / 2);
-        Some(result)
+        // the triangular cost is minimised within half a step of the mean, so
+        // only the positions either side of it need checking
+        let sum: u64 = self.positions.iter().sum();
+        let mean = sum / self.positions.len() as u64;
+        let low = mean.saturating_sub(1).max(self.min_position);
+        let high = (mean + 1).min(self.max_position);
+        (low..=high).map(|i| self.fuel(i, triangular)).min()
     }
 }
 
+fn linear(distance: u64) -> u64 {
+    distance
+}
+
+fn triangular(distance: u64) -> u64 {
+    distance * (distance + 1) / 2
+}
+
 impl Day7Solver {
-    fn solve<F>(&self, cost: F) -> u64
+    /// The total fuel needed to move every crab to a target position.
+    fn fuel<F>(&self, target: u64, cost: F) -> u64
     where
         F: Fn(u64) -> u64,
     {
-        (self.min_position..self.max_position)
-            .map(|i| {
-                self.positions
-                    .iter()
-                    .map(|j| cost(i64::abs(i as i64 - *j as i64) as u64))
-                    .sum()
-            })
-            .min()
-            .unwrap()
+        self.positions
+            .iter()
+            .map(|position| cost(target.abs_diff(*position)))
+            .sum()
+    }
+
+    /// Find the minimum fuel for any convex cost function, using a ternary
+    /// search on the slope of the fuel curve.
+    fn search<F>(&self, cost: F) -> u64
+    where
+        F: Fn(u64) -> u64,
+    {
+        let (mut low, mut high) = (self.min_position, self.max_position);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.fuel(mid, &cost) <= self.fuel(mid + 1, &cost) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        self.fuel(low, cost)
+    }
+
+    /// The fuel needed to align at every position between the outermost crabs.
+    fn curve<F>(&self, cost: F) -> impl Iterator<Item = (u64, u64)> + '_
+    where
+        F: Fn(u64) -> u64 + 'static,
+    {
+        (self.min_position..=self.max_position).map(move |i| (i, self.fuel(i, &cost)))
+    }
+
+    fn write_curve<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "position,linear,triangular")?;
+        for ((position, linear), (_, triangular)) in self.curve(linear).zip(self.curve(triangular))
+        {
+            writeln!(w, "{},{},{}", position, linear, triangular)?;
+        }
+        Ok(())
     }
 }
 
@@ -82,8 +131,44 @@ mod day7tests {
         };
         assert_eq!(solver.solve2(), Some(168));
     }
+
+    #[test]
+    fn test_search() {
+        let solver = Day7Solver::new(EXAMPLE_DATA);
+        assert_eq!(solver.search(linear), 37);
+        assert_eq!(solver.search(triangular), 168);
+        assert_eq!(solver.search(|cost| cost * cost), 291);
+
+        // the optimum can sit on the outermost crab
+        let solver = Day7Solver::new("0,5,5,5");
+        assert_eq!(solver.solve1(), Some(5));
+        assert_eq!(solver.search(linear), 5);
+        assert_eq!(solver.curve(linear).last(), Some((5, 5)));
+    }
+
+    #[test]
+    fn test_curve() {
+        let solver = Day7Solver::new("1,3");
+        let mut output = vec![];
+        solver.write_curve(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "position,linear,triangular\n1,2,3\n2,2,2\n3,2,3\n"
+        );
+    }
 }
 
 fn main() {
-    solve_file::<Day7Solver, u64>("day7.txt");
+    let curve = std::env::args().find_map(|arg| arg.strip_prefix("--curve=").map(String::from));
+    if let Some(path) = curve {
+        let solver = Day7Solver::new(&load_file("day7.txt"));
+        let mut file = BufWriter::new(File::create(path).unwrap());
+        solver.write_curve(&mut file).unwrap();
+    } else if std::env::args().any(|arg| arg == "--search") {
+        let solver = Day7Solver::new(&load_file("day7.txt"));
+        println!("part 1: {}", solver.search(linear));
+        println!("part 2: {}", solver.search(triangular));
+    } else {
+        solve_file::<Day7Solver, u64>("day7.txt");
+    }
 }
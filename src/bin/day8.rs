@@ -1,6 +1,5 @@
-use advent_of_code_2021::solver::{solve_file, Solver};
-
-use itertools::Itertools;
+use advent_of_code_2021::solver::{load_file, solve_file, Solver};
+use std::fmt;
 
 struct Day8Solver {
     puzzles: Vec<Puzzle>,
@@ -13,21 +12,330 @@ impl Solver<u64> for Day8Solver {
     }
 
     fn solve1(&self) -> Option<u64> {
-        let result = self
+        let digits = self
             .puzzles
             .iter()
-            .flat_map(Puzzle::solve)
-            .filter(|n| *n == 1 || *n == 4 || *n == 7 || *n == 8)
+            .map(Puzzle::solve)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let result = digits
+            .iter()
+            .flatten()
+            .filter(|n| **n == 1 || **n == 4 || **n == 7 || **n == 8)
             .count();
         Some(result as u64)
     }
 
     fn solve2(&self) -> Option<u64> {
-        let result = self.puzzles.iter().map(Puzzle::solve_n).sum();
-        Some(result)
+        self.puzzles
+            .iter()
+            .map(|puzzle| puzzle.solve_n().ok())
+            .sum()
+    }
+}
+
+impl Day8Solver {
+    fn print_wiring(&self) {
+        let layout = Layout::seven_segment();
+        for (i, puzzle) in self.puzzles.iter().enumerate() {
+            match puzzle.decode(&layout) {
+                Ok(decoded) => {
+                    let wiring: Vec<String> = decoded
+                        .wiring
+                        .iter()
+                        .map(|(wire, segment)| format!("{}->{}", wire, segment))
+                        .collect();
+                    let value: String =
+                        decoded.glyphs.iter().map(|g| layout.glyphs[*g].0).collect();
+                    println!("entry {}: {} = {}", i + 1, wiring.join(" "), value);
+
+                    let glyphs: Vec<u32> =
+                        decoded.glyphs.iter().map(|g| layout.glyphs[*g].1).collect();
+                    println!("{}", render_seven_segment(&glyphs));
+                }
+                Err(err) => println!("entry {}: {}\n", i + 1, err),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DecodeError {
+    UnknownWire(char),
+    UnknownPattern(String),
+    Impossible,
+    Ambiguous,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownWire(wire) => write!(f, "unknown wire {}", wire),
+            DecodeError::UnknownPattern(pattern) => {
+                write!(f, "{} doesn't match any glyph", pattern)
+            }
+            DecodeError::Impossible => write!(f, "no wiring matches the samples"),
+            DecodeError::Ambiguous => write!(f, "more than one wiring matches the samples"),
+        }
+    }
+}
+
+/// The segments of a kind of display, and the glyphs it can show. Segments
+/// and glyphs are stored as bitmasks, so there can be at most 32 segments
+/// and 64 glyphs.
+struct Layout {
+    segments: Vec<char>,
+    glyphs: Vec<(char, u32)>,
+}
+
+impl Layout {
+    fn new(segments: &str, glyphs: &[(char, &str)]) -> Self {
+        let segments: Vec<char> = segments.chars().collect();
+        assert!(segments.len() <= 32, "too many segments");
+        assert!(glyphs.len() <= 64, "too many glyphs");
+
+        let mut layout = Self {
+            segments,
+            glyphs: vec![],
+        };
+        layout.glyphs = glyphs
+            .iter()
+            .map(|(symbol, pattern)| (*symbol, layout.mask(pattern).unwrap()))
+            .collect();
+        layout
+    }
+
+    fn seven_segment() -> Self {
+        Self::new(
+            "abcdefg",
+            &[
+                ('0', "abcefg"),
+                ('1', "cf"),
+                ('2', "acdeg"),
+                ('3', "acdfg"),
+                ('4', "bcdf"),
+                ('5', "abdfg"),
+                ('6', "abdefg"),
+                ('7', "acf"),
+                ('8', "abcdefg"),
+                ('9', "abcdfg"),
+            ],
+        )
+    }
+
+    fn all(&self) -> u32 {
+        (u64::MAX >> (64 - self.segments.len())) as u32
+    }
+
+    fn mask(&self, pattern: &str) -> Result<u32, DecodeError> {
+        pattern.chars().try_fold(0, |mask, ch| {
+            let i = self
+                .segments
+                .iter()
+                .position(|segment| *segment == ch)
+                .ok_or(DecodeError::UnknownWire(ch))?;
+            Ok(mask | 1 << i)
+        })
+    }
+
+    fn glyph(&self, segments: u32) -> Option<usize> {
+        self.glyphs.iter().position(|(_, glyph)| *glyph == segments)
+    }
+
+    /// Find the only wiring, from each wire to the segment it lights, that
+    /// makes every sample a valid glyph.
+    fn decode(&self, samples: &[String]) -> Result<Vec<usize>, DecodeError> {
+        let mut patterns = samples
+            .iter()
+            .map(|sample| self.mask(sample))
+            .collect::<Result<Vec<_>, _>>()?;
+        patterns.sort_unstable();
+        patterns.dedup();
+
+        let glyphs = u64::MAX >> (64 - self.glyphs.len());
+        let state = Deduction {
+            layout: self,
+            patterns: &patterns,
+            wires: vec![self.all(); self.segments.len()],
+            candidates: vec![glyphs; patterns.len()],
+        };
+
+        let mut solutions = vec![];
+        state.search(&mut solutions);
+        match &solutions[..] {
+            [] => Err(DecodeError::Impossible),
+            [wiring] => Ok(wiring.clone()),
+            _ => Err(DecodeError::Ambiguous),
+        }
     }
 }
 
+/// The segments each wire could still light, and the glyphs each sample
+/// could still be.
+#[derive(Clone)]
+struct Deduction<'a> {
+    layout: &'a Layout,
+    patterns: &'a [u32],
+    wires: Vec<u32>,
+    candidates: Vec<u64>,
+}
+
+impl<'a> Deduction<'a> {
+    /// Narrow down the possibilities until nothing changes, returning false
+    /// if there's a contradiction.
+    fn propagate(&mut self) -> bool {
+        let all = self.layout.all();
+        loop {
+            let before = (self.wires.clone(), self.candidates.clone());
+
+            for (i, pattern) in self.patterns.iter().enumerate() {
+                // a sample can only be a glyph with the same number of
+                // segments, that its wires could all light
+                let wires = &self.wires;
+                let fits = |glyph: u32| {
+                    glyph.count_ones() == pattern.count_ones()
+                        && wires.iter().enumerate().all(|(w, segments)| {
+                            let target = if pattern & 1 << w != 0 {
+                                glyph
+                            } else {
+                                all & !glyph
+                            };
+                            segments & target != 0
+                        })
+                };
+                for (g, (_, glyph)) in self.layout.glyphs.iter().enumerate() {
+                    if self.candidates[i] & 1 << g != 0 && !fits(*glyph) {
+                        self.candidates[i] &= !(1 << g);
+                    }
+                }
+                if self.candidates[i] == 0 {
+                    return false;
+                }
+
+                // wires in the sample light one of its candidates' segments,
+                // and the rest light one of the segments they leave out
+                let (mut lit, mut unlit) = (0, 0);
+                for (g, (_, glyph)) in self.layout.glyphs.iter().enumerate() {
+                    if self.candidates[i] & 1 << g != 0 {
+                        lit |= glyph;
+                        unlit |= all & !glyph;
+                    }
+                }
+                for (w, segments) in self.wires.iter_mut().enumerate() {
+                    *segments &= if pattern & 1 << w != 0 { lit } else { unlit };
+                }
+            }
+
+            // different samples are different glyphs
+            for i in 0..self.candidates.len() {
+                if self.candidates[i].count_ones() == 1 {
+                    for j in 0..self.candidates.len() {
+                        if i != j {
+                            self.candidates[j] &= !self.candidates[i];
+                        }
+                    }
+                }
+            }
+
+            // each segment is lit by exactly one wire
+            for w in 0..self.wires.len() {
+                if self.wires[w].count_ones() == 1 {
+                    for v in 0..self.wires.len() {
+                        if v != w {
+                            self.wires[v] &= !self.wires[w];
+                        }
+                    }
+                }
+            }
+            for segment in 0..self.wires.len() {
+                let mut options =
+                    (0..self.wires.len()).filter(|w| self.wires[*w] & 1 << segment != 0);
+                if let (Some(w), None) = (options.next(), options.next()) {
+                    self.wires[w] = 1 << segment;
+                }
+            }
+
+            if self.wires.contains(&0) || self.candidates.contains(&0) {
+                return false;
+            }
+            if before == (self.wires.clone(), self.candidates.clone()) {
+                return true;
+            }
+        }
+    }
+
+    /// Collect wirings that satisfy every sample, stopping once there's more
+    /// than one.
+    fn search(mut self, solutions: &mut Vec<Vec<usize>>) {
+        if solutions.len() > 1 || !self.propagate() {
+            return;
+        }
+
+        // guess for the wire with the fewest options left
+        let undecided = (0..self.wires.len())
+            .filter(|w| self.wires[*w].count_ones() > 1)
+            .min_by_key(|w| self.wires[*w].count_ones());
+        let w = match undecided {
+            Some(w) => w,
+            None => {
+                let wiring = self
+                    .wires
+                    .iter()
+                    .map(|segments| segments.trailing_zeros() as usize);
+                solutions.push(wiring.collect());
+                return;
+            }
+        };
+        for segment in 0..self.layout.segments.len() {
+            if self.wires[w] & 1 << segment != 0 {
+                let mut guess = self.clone();
+                guess.wires[w] = 1 << segment;
+                guess.search(solutions);
+            }
+        }
+    }
+}
+
+/// A decoded entry: which segment each wire lights, and the index of each
+/// target glyph in the layout.
+#[derive(Debug, PartialEq, Eq)]
+struct Decoded {
+    wiring: Vec<(char, char)>,
+    glyphs: Vec<usize>,
+}
+
+/// Draw seven-segment glyphs side by side, in the puzzle's style of showing
+/// lit segments by name and unlit ones as dots.
+fn render_seven_segment(glyphs: &[u32]) -> String {
+    // the segments drawn on each row, either one across or two down
+    const ROWS: [&[usize]; 7] = [&[0], &[1, 2], &[1, 2], &[3], &[4, 5], &[4, 5], &[6]];
+
+    let segment = |glyph: u32, i: usize| {
+        if glyph & 1 << i != 0 {
+            (b'a' + i as u8) as char
+        } else {
+            '.'
+        }
+    };
+
+    let mut output = String::new();
+    for row in ROWS {
+        let line: Vec<String> = glyphs
+            .iter()
+            .map(|glyph| match row {
+                [across] => format!(" {0}{0}{0}{0} ", segment(*glyph, *across)),
+                [left, right] => {
+                    format!("{}    {}", segment(*glyph, *left), segment(*glyph, *right))
+                }
+                _ => unreachable!(),
+            })
+            .collect();
+        output += line.join("  ").trim_end();
+        output += "\n";
+    }
+    output
+}
+
 struct Puzzle {
     samples: Vec<String>,
     target: Vec<String>,
@@ -41,63 +349,45 @@ impl Puzzle {
         Self { samples, target }
     }
 
-    fn solve(&self) -> Vec<u8> {
-        let collection = vec![
-            "abcefg".to_string(),
-            "cf".to_string(),
-            "acdeg".to_string(),
-            "acdfg".to_string(),
-            "bcdf".to_string(),
-            "abdfg".to_string(),
-            "abdefg".to_string(),
-            "acf".to_string(),
-            "abcdefg".to_string(),
-            "abcdfg".to_string(),
-        ];
-
-        let basis: Vec<char> = "abcdefg".chars().collect();
-        let result = basis
-            .clone()
-            .into_iter()
-            .permutations(basis.len())
-            .find(|reorder| {
-                self.samples.iter().all(|sample| {
-                    collection
-                        .iter()
-                        .any(|valid| translate(&basis, reorder, sample) == *valid)
-                })
-            })
-            .unwrap();
-
-        self.target
+    /// Work out the wiring from the samples, and use it to find the index of
+    /// each target glyph in the layout.
+    fn decode(&self, layout: &Layout) -> Result<Decoded, DecodeError> {
+        let wiring = layout.decode(&self.samples)?;
+        let glyphs = self
+            .target
             .iter()
             .map(|target| {
-                collection
-                    .iter()
-                    .position(|valid| translate(&basis, &result, target) == *valid)
-                    .unwrap() as u8
+                let pattern = layout.mask(target)?;
+                let segments = (0..wiring.len())
+                    .filter(|w| pattern & 1 << w != 0)
+                    .fold(0, |segments, w| segments | 1 << wiring[w]);
+                layout
+                    .glyph(segments)
+                    .ok_or_else(|| DecodeError::UnknownPattern(target.clone()))
             })
-            .collect()
+            .collect::<Result<_, _>>()?;
+
+        let wiring = wiring
+            .iter()
+            .enumerate()
+            .map(|(w, segment)| (layout.segments[w], layout.segments[*segment]))
+            .collect();
+        Ok(Decoded { wiring, glyphs })
     }
 
-    fn solve_n(&self) -> u64 {
-        self.solve()
+    fn solve(&self) -> Result<Vec<u8>, DecodeError> {
+        let decoded = self.decode(&Layout::seven_segment())?;
+        Ok(decoded
+            .glyphs
             .into_iter()
-            .fold(0, |acc, n| acc * 10 + n as u64)
+            .map(|digit| digit as u8)
+            .collect())
     }
-}
 
-fn translate(basis: &[char], reorder: &[char], target: &str) -> String {
-    target
-        .chars()
-        .map(|ch| {
-            basis
-                .iter()
-                .nth(reorder.iter().position(|x| *x == ch).unwrap())
-                .unwrap()
-        })
-        .sorted()
-        .collect()
+    fn solve_n(&self) -> Result<u64, DecodeError> {
+        let digits = self.solve()?;
+        Ok(digits.into_iter().fold(0, |acc, n| acc * 10 + n as u64))
+    }
 }
 
 #[cfg(test)]
@@ -141,7 +431,110 @@ mod day8tests {
             samples: EXAMPLE_SAMPLES.map(str::to_string).to_vec(),
             target: EXAMPLE_TARGET.map(str::to_string).to_vec(),
         };
-        assert_eq!(puzzle.solve(), [8, 3, 9, 4]);
+        assert_eq!(puzzle.solve(), Ok(vec![8, 3, 9, 4]));
+    }
+
+    #[test]
+    fn test_puzzle_wiring() {
+        let puzzle = Puzzle::new(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let decoded = puzzle.decode(&Layout::seven_segment()).unwrap();
+        assert_eq!(
+            decoded.wiring,
+            vec![
+                ('a', 'c'),
+                ('b', 'f'),
+                ('c', 'g'),
+                ('d', 'a'),
+                ('e', 'b'),
+                ('f', 'd'),
+                ('g', 'e')
+            ]
+        );
+        assert_eq!(decoded.glyphs, vec![5, 3, 5, 3]);
+    }
+
+    #[test]
+    fn test_render() {
+        let layout = Layout::seven_segment();
+        let glyphs = [layout.glyphs[0].1, layout.glyphs[1].1, layout.glyphs[4].1];
+        assert_eq!(
+            render_seven_segment(&glyphs),
+            indoc! {"
+                 aaaa    ....    ....
+                b    c  .    c  b    c
+                b    c  .    c  b    c
+                 ....    ....    dddd
+                e    f  .    f  .    f
+                e    f  .    f  .    f
+                 gggg    ....    ....
+            "}
+        );
+    }
+
+    #[test]
+    fn test_puzzle_errors() {
+        let puzzle = Puzzle::new("ab cd | ab");
+        assert_eq!(puzzle.solve(), Err(DecodeError::Impossible));
+        let puzzle = Puzzle::new("ab abcdefg | ab");
+        assert_eq!(puzzle.solve(), Err(DecodeError::Ambiguous));
+        let puzzle = Puzzle::new("ab xy | ab");
+        assert_eq!(puzzle.solve(), Err(DecodeError::UnknownWire('x')));
+
+        let mut puzzle = Puzzle::new(EXAMPLE_DATA);
+        puzzle.target[0] = "fd".to_string();
+        assert_eq!(
+            puzzle.solve(),
+            Err(DecodeError::UnknownPattern("fd".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_fourteen_segment() {
+        let glyphs = [
+            ('0', "abcdefkl"),
+            ('1', "bck"),
+            ('2', "abdegh"),
+            ('3', "abcdh"),
+            ('4', "bcfgh"),
+            ('5', "adfgn"),
+            ('6', "acdefgh"),
+            ('7', "akm"),
+            ('8', "abcdefgh"),
+            ('9', "abcdfgh"),
+            ('A', "abcefgh"),
+            ('M', "bcefik"),
+            ('N', "bcefin"),
+            ('T', "ajm"),
+            ('W', "bcefln"),
+            ('X', "ikln"),
+        ];
+        let layout = Layout::new("abcdefghijklmn", &glyphs);
+
+        // scramble the wires, and check they can be recovered
+        let wires: Vec<char> = "kbnaimhcfjgdle".chars().collect();
+        let scramble = |glyph: &str| -> String {
+            glyph
+                .chars()
+                .map(|ch| wires[ch as usize - 'a' as usize])
+                .collect()
+        };
+        let puzzle = Puzzle {
+            samples: glyphs.iter().map(|(_, glyph)| scramble(glyph)).collect(),
+            target: ["bcefln", "abcefgh", "ajm", "abcdh"]
+                .iter()
+                .map(|glyph| scramble(glyph))
+                .collect(),
+        };
+        let symbols: String = puzzle
+            .decode(&layout)
+            .unwrap()
+            .glyphs
+            .into_iter()
+            .map(|i| layout.glyphs[i].0)
+            .collect();
+        assert_eq!(symbols, "WAT3");
     }
 
     #[test]
@@ -158,5 +551,10 @@ mod day8tests {
 }
 
 fn main() {
-    solve_file::<Day8Solver, u64>("day8.txt");
+    if std::env::args().any(|arg| arg == "--wiring") {
+        let solver = Day8Solver::new(load_file("day8.txt").trim());
+        solver.print_wiring();
+    } else {
+        solve_file::<Day8Solver, u64>("day8.txt");
+    }
 }